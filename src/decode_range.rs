@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use super::*;
+
+/// A job that can be aborted. This lets the bookkeeping below be tested without the SDK.
+trait AbortJob: Send {
+    fn abort_job(&mut self);
+}
+
+impl AbortJob for Job {
+    fn abort_job(&mut self) {
+        let _ = self.abort();
+    }
+}
+
+/// The frames that have been started and yielded so far.
+struct Window {
+    next_to_submit: u64,
+    next_to_yield: u64,
+    end: u64,
+    max_in_flight: u64,
+}
+
+impl Window {
+    /// Returns the next frame to start, unless `max_in_flight` frames are already either in
+    /// progress or waiting to be consumed.
+    fn start_next(&mut self) -> Option<u64> {
+        if self.next_to_submit >= self.end || self.next_to_submit - self.next_to_yield >= self.max_in_flight {
+            return None;
+        }
+        self.next_to_submit += 1;
+        Some(self.next_to_submit - 1)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.next_to_yield >= self.end
+    }
+}
+
+struct RangeState<J = Job> {
+    completed: HashMap<u64, Result<Image, Error>>,
+    // The job currently working on each outstanding frame, so that they can be aborted.
+    jobs: HashMap<u64, J>,
+    stopped: bool,
+    waker: Option<Waker>,
+}
+
+impl<J> RangeState<J> {
+    /// Takes the next frame's result if it's ready, so that frames are yielded in order.
+    fn take_next(&mut self, window: &mut Window) -> Option<Result<Image, Error>> {
+        let result = self.completed.remove(&window.next_to_yield);
+        if result.is_some() {
+            window.next_to_yield += 1;
+        }
+        result
+    }
+}
+
+struct Shared<J = Job> {
+    state: Mutex<RangeState<J>>,
+    ready: Condvar,
+}
+
+impl<J: AbortJob> Shared<J> {
+    fn new() -> Shared<J> {
+        Shared{
+            state: Mutex::new(RangeState{
+                completed: HashMap::new(),
+                jobs: HashMap::new(),
+                stopped: false,
+                waker: None,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RangeState<J>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Aborts the outstanding jobs, as well as any that are tracked afterwards.
+    fn stop(&self) {
+        let jobs: Vec<J> = {
+            let mut state = self.lock();
+            state.stopped = true;
+            state.jobs.drain().map(|(_, job)| job).collect()
+        };
+        for mut job in jobs {
+            job.abort_job();
+        }
+    }
+}
+
+/// The result of one frame's read, decode, and process jobs. If it's dropped without completing,
+/// for example because the SDK abandoned a job, the frame fails with `ErrorCode::Abort`.
+struct FrameSlot<J: AbortJob = Job> {
+    frame: u64,
+    shared: Arc<Shared<J>>,
+    done: AtomicBool,
+}
+
+impl<J: AbortJob> FrameSlot<J> {
+    fn complete(&self, result: Result<Image, Error>) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let (job, waker) = {
+            let mut state = self.shared.lock();
+            state.completed.insert(self.frame, result);
+            (state.jobs.remove(&self.frame), state.waker.take())
+        };
+        drop(job);
+        self.shared.ready.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Records the decode job that's now working on the frame, replacing its read job, unless the
+    /// frame has already completed.
+    fn track(&self, job: J) {
+        self.track_job(job, true)
+    }
+
+    /// Records the frame's read job. It's called once the read has been submitted, by which time
+    /// the read may already have completed and its decode job been tracked, so it doesn't replace
+    /// an existing job.
+    fn track_read(&self, job: J) {
+        self.track_job(job, false)
+    }
+
+    fn track_job(&self, mut job: J, replace: bool) {
+        let mut state = self.shared.lock();
+        if self.done.load(Ordering::SeqCst) {
+            return;
+        }
+        if state.stopped {
+            drop(state);
+            job.abort_job();
+            return;
+        }
+        if replace || !state.jobs.contains_key(&self.frame) {
+            state.jobs.insert(self.frame, job);
+        }
+    }
+}
+
+impl<J: AbortJob> Drop for FrameSlot<J> {
+    fn drop(&mut self) {
+        self.complete(Err(Error::new(ErrorCode::Abort, "IBlackmagicRawCallback::ProcessComplete")));
+    }
+}
+
+/// Decodes a range of frames, yielding them in order. See `Clip::decode_range`.
+///
+/// This is both a blocking `Iterator` and a `Stream`. Dropping it aborts any outstanding jobs. As
+/// with `Clip::decode_frame`, iterating it from a callback or job handler fails with
+/// `ErrorCode::Unexpected`.
+pub struct DecodeRange {
+    clip: Clip,
+    format: ResourceFormat,
+    resolution_scale: Option<ResolutionScale>,
+    window: Window,
+    shared: Arc<Shared>,
+}
+
+impl DecodeRange {
+    /// Decodes the frames at a reduced resolution. This must be set before the first frame is
+    /// requested.
+    pub fn resolution_scale(mut self, scale: ResolutionScale) -> Self {
+        self.resolution_scale = Some(scale);
+        self
+    }
+
+    /// Starts read jobs until `max_in_flight` frames are either in progress or waiting to be
+    /// consumed.
+    fn fill(&mut self) {
+        while let Some(frame) = self.window.start_next() {
+            let slot = Arc::new(FrameSlot{
+                frame: frame,
+                shared: self.shared.clone(),
+                done: AtomicBool::new(false),
+            });
+
+            let format = self.format;
+            let resolution_scale = self.resolution_scale;
+            let read_slot = slot.clone();
+            let job = self.clip.create_job_read_frame_with(slot.frame, move |_job, result| {
+                let slot = read_slot;
+                let submitted = result.and_then(|mut frame| {
+                    frame.set_resource_format(format)?;
+                    if let Some(scale) = resolution_scale {
+                        frame.set_resolution_scale(scale)?;
+                    }
+                    let process_slot = slot.clone();
+                    let mut job = frame.create_job_decode_and_process_frame_with(None, None, move |_job, result| {
+                        process_slot.complete(result.and_then(|mut image| image.to_image()));
+                    })?;
+                    job.submit()?;
+                    Ok(job)
+                });
+                match submitted {
+                    Ok(job) => slot.track(job),
+                    Err(e) => slot.complete(Err(e)),
+                }
+            }).and_then(|mut job| {
+                job.submit()?;
+                Ok(job)
+            });
+            match job {
+                Ok(job) => slot.track_read(job),
+                Err(e) => slot.complete(Err(e)),
+            }
+        }
+    }
+}
+
+impl Iterator for DecodeRange {
+    type Item = Result<Image, Error>;
+
+    fn next(&mut self) -> Option<Result<Image, Error>> {
+        if self.window.is_finished() {
+            return None;
+        }
+        // Waiting here would block the SDK thread that's needed to complete the frame.
+        if in_callback() {
+            return Some(Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawClip::CreateJobReadFrame")));
+        }
+        self.fill();
+        let shared = self.shared.clone();
+        let mut state = shared.lock();
+        loop {
+            if let Some(result) = state.take_next(&mut self.window) {
+                drop(state);
+                self.fill();
+                return Some(result);
+            }
+            state = shared.ready.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl Stream for DecodeRange {
+    type Item = Result<Image, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Image, Error>>> {
+        let this = self.get_mut();
+        if this.window.is_finished() {
+            return Poll::Ready(None);
+        }
+        this.fill();
+        let shared = this.shared.clone();
+        let mut state = shared.lock();
+        match state.take_next(&mut this.window) {
+            Some(result) => {
+                drop(state);
+                this.fill();
+                Poll::Ready(Some(result))
+            },
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl Drop for DecodeRange {
+    fn drop(&mut self) {
+        self.shared.stop();
+    }
+}
+
+impl Clip {
+    /// Reads, decodes, and processes the frames in `range`, yielding them in order. Up to
+    /// `max_in_flight` frames are decoded concurrently, and no more are started until the consumer
+    /// catches up. As with `decode_frame`, the codec must use a CPU pipeline.
+    pub fn decode_range(&mut self, range: Range<u64>, format: ResourceFormat, max_in_flight: usize) -> DecodeRange {
+        DecodeRange{
+            clip: unsafe { Clip::new_ref(self.implementation) },
+            format: format,
+            resolution_scale: None,
+            window: Window{
+                next_to_submit: range.start,
+                next_to_yield: range.start,
+                end: std::cmp::max(range.end, range.start),
+                max_in_flight: std::cmp::max(max_in_flight, 1) as u64,
+            },
+            shared: Arc::new(Shared::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestJob {
+        name: &'static str,
+        aborted: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl AbortJob for TestJob {
+        fn abort_job(&mut self) {
+            self.aborted.lock().unwrap().push(self.name);
+        }
+    }
+
+    fn slot(shared: &Arc<Shared<TestJob>>, frame: u64) -> FrameSlot<TestJob> {
+        FrameSlot{
+            frame: frame,
+            shared: shared.clone(),
+            done: AtomicBool::new(false),
+        }
+    }
+
+    fn image(width: u32) -> Result<Image, Error> {
+        Ok(Image{
+            width: width,
+            height: 1,
+            format: ResourceFormat::FORMAT_RGBAU8,
+            data: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn frames_are_yielded_in_order_with_backpressure() {
+        let shared = Arc::new(Shared::<TestJob>::new());
+        let mut window = Window{
+            next_to_submit: 10,
+            next_to_yield: 10,
+            end: 14,
+            max_in_flight: 2,
+        };
+        assert_eq!(window.start_next(), Some(10));
+        assert_eq!(window.start_next(), Some(11));
+        assert_eq!(window.start_next(), None);
+
+        slot(&shared, 11).complete(image(11));
+        assert!(shared.lock().take_next(&mut window).is_none());
+        assert_eq!(window.start_next(), None);
+
+        slot(&shared, 10).complete(image(10));
+        assert_eq!(shared.lock().take_next(&mut window).unwrap().unwrap().width, 10);
+        assert_eq!(window.start_next(), Some(12));
+        assert_eq!(window.start_next(), None);
+        assert_eq!(shared.lock().take_next(&mut window).unwrap().unwrap().width, 11);
+        assert_eq!(window.start_next(), Some(13));
+        assert_eq!(window.start_next(), None);
+
+        // A slot that's dropped without completing fails its frame.
+        drop(slot(&shared, 12));
+        assert_eq!(shared.lock().take_next(&mut window).unwrap().err().unwrap().code(), Some(ErrorCode::Abort));
+        assert!(!window.is_finished());
+        slot(&shared, 13).complete(image(13));
+        assert_eq!(shared.lock().take_next(&mut window).unwrap().unwrap().width, 13);
+        assert!(window.is_finished());
+    }
+
+    #[test]
+    fn stopping_aborts_outstanding_jobs() {
+        let aborted = Arc::new(Mutex::new(Vec::new()));
+        let job = |name| TestJob{
+            name: name,
+            aborted: aborted.clone(),
+        };
+        let shared = Arc::new(Shared::<TestJob>::new());
+
+        // The read completes and its decode job is tracked before the read job itself is.
+        let racing = slot(&shared, 0);
+        racing.track(job("decode 0"));
+        racing.track_read(job("read 0"));
+
+        let reading = slot(&shared, 1);
+        reading.track_read(job("read 1"));
+
+        let finished = slot(&shared, 2);
+        finished.complete(image(2));
+        finished.track(job("decode 2"));
+
+        shared.stop();
+        let mut stopped = aborted.lock().unwrap().clone();
+        stopped.sort();
+        assert_eq!(stopped, vec!["decode 0", "read 1"]);
+
+        reading.track(job("decode 1"));
+        assert_eq!(aborted.lock().unwrap().last(), Some(&"decode 1"));
+        assert!(shared.lock().jobs.is_empty());
+    }
+
+    #[test]
+    fn iterating_from_a_callback_fails() {
+        // The clip is never used, since nothing is submitted from a callback.
+        let mut range = DecodeRange{
+            clip: Clip{
+                implementation: std::ptr::null_mut(),
+            },
+            format: ResourceFormat::FORMAT_RGBAU8,
+            resolution_scale: None,
+            window: Window{
+                next_to_submit: 0,
+                next_to_yield: 0,
+                end: 1,
+                max_in_flight: 1,
+            },
+            shared: Arc::new(Shared::new()),
+        };
+        IN_CALLBACK.with(|c| c.set(true));
+        let result = range.next();
+        IN_CALLBACK.with(|c| c.set(false));
+        assert_eq!(result.unwrap().err().unwrap().code(), Some(ErrorCode::Unexpected));
+        assert_eq!(range.window.next_to_submit, 0);
+        // Dropping it would release the null clip.
+        std::mem::forget(range);
+    }
+}
@@ -0,0 +1,186 @@
+//! Exact frame rates, and conversions between frame indices, presentation times, and audio sample
+//! indices.
+
+use std::time::Duration;
+
+use super::*;
+
+/// A frame rate as a fraction of frames per second, such as 24000/1001 for 23.976 fps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameRate {
+    numerator: u32,
+    denominator: u32,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl FrameRate {
+    pub const FPS_23_976: FrameRate = FrameRate{ numerator: 24000, denominator: 1001 };
+    pub const FPS_24: FrameRate = FrameRate{ numerator: 24, denominator: 1 };
+    pub const FPS_25: FrameRate = FrameRate{ numerator: 25, denominator: 1 };
+    pub const FPS_29_97: FrameRate = FrameRate{ numerator: 30000, denominator: 1001 };
+    pub const FPS_30: FrameRate = FrameRate{ numerator: 30, denominator: 1 };
+    pub const FPS_50: FrameRate = FrameRate{ numerator: 50, denominator: 1 };
+    pub const FPS_59_94: FrameRate = FrameRate{ numerator: 60000, denominator: 1001 };
+    pub const FPS_60: FrameRate = FrameRate{ numerator: 60, denominator: 1 };
+
+    /// Creates a frame rate of `numerator / denominator` frames per second, reduced to lowest
+    /// terms. Returns `None` if either is zero.
+    pub fn new(numerator: u32, denominator: u32) -> Option<FrameRate> {
+        if numerator == 0 || denominator == 0 {
+            return None;
+        }
+        let d = gcd(numerator as u64, denominator as u64) as u32;
+        Some(FrameRate{
+            numerator: numerator / d,
+            denominator: denominator / d,
+        })
+    }
+
+    /// Snaps an approximate rate, such as the SDK's 23.976, to the nearest integer or NTSC
+    /// (N × 1000/1001) rate. Other rates are rounded to the nearest thousandth of a frame.
+    pub fn from_f32(fps: f32) -> Option<FrameRate> {
+        let fps = fps as f64;
+        if !fps.is_finite() || fps <= 0.0 || fps > u32::MAX as f64 / 1000.0 {
+            return None;
+        }
+        let tolerance = 0.0005 * fps.max(1.0);
+        let integer = fps.round();
+        if (fps - integer).abs() < tolerance {
+            return FrameRate::new(integer as u32, 1);
+        }
+        let ntsc = (fps * 1.001).round();
+        if (fps - ntsc * 1000.0 / 1001.0).abs() < tolerance {
+            return FrameRate::new(ntsc as u32 * 1000, 1001);
+        }
+        FrameRate::new((fps * 1000.0).round() as u32, 1000)
+    }
+
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// The rate rounded to a whole number of frames, as used for timecode. For example, this is 30
+    /// for 29.97 fps.
+    pub fn nominal(&self) -> u32 {
+        (self.numerator + self.denominator / 2) / self.denominator
+    }
+
+    /// Returns the presentation time of a frame, relative to the first frame. It's rounded up to the
+    /// next nanosecond, so that `time_to_frame` maps it back to the same frame.
+    pub fn frame_to_time(&self, frame_index: u64) -> Duration {
+        let nanos = (frame_index as u128 * self.denominator as u128 * 1_000_000_000).div_ceil(self.numerator as u128);
+        Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+    }
+
+    /// Returns the index of the frame being presented at `time`.
+    pub fn time_to_frame(&self, time: Duration) -> u64 {
+        (time.as_nanos() * self.numerator as u128 / (self.denominator as u128 * 1_000_000_000)) as u64
+    }
+
+    /// Returns the index of the first audio sample that's presented with a frame, or `None` if
+    /// `sample_rate` is zero.
+    pub fn frame_to_sample(&self, frame_index: u64, sample_rate: u32) -> Option<u64> {
+        if sample_rate == 0 {
+            return None;
+        }
+        Some((frame_index as u128 * self.denominator as u128 * sample_rate as u128).div_ceil(self.numerator as u128) as u64)
+    }
+
+    /// Returns the index of the frame being presented with an audio sample, or `None` if
+    /// `sample_rate` is zero.
+    pub fn sample_to_frame(&self, sample_index: u64, sample_rate: u32) -> Option<u64> {
+        if sample_rate == 0 {
+            return None;
+        }
+        Some((sample_index as u128 * self.numerator as u128 / (self.denominator as u128 * sample_rate as u128)) as u64)
+    }
+}
+
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl Clip {
+    /// Returns the clip's exact frame rate. The "sensor_rate" metadata is used if it agrees with
+    /// `get_frame_rate`, which it won't for off-speed clips, and otherwise that is snapped to the
+    /// nearest standard rate.
+    pub fn frame_rate(&mut self) -> Result<FrameRate, Error> {
+        let fps = self.get_frame_rate()?;
+        let snapped = FrameRate::from_f32(fps).ok_or_else(|| Error::new(ErrorCode::Unexpected, "IBlackmagicRawClip::GetFrameRate"))?;
+        // The metadata is only a refinement, so failing to read it isn't an error.
+        let sensor_rate = self.get_metadata("sensor_rate").ok().and_then(|value| match value {
+            Some(Value::Array(ref v)) if v.len() == 2 => match (v[0].to_f64(), v[1].to_f64()) {
+                (Some(n), Some(d)) if n >= 1.0 && d >= 1.0 && n <= u32::MAX as f64 && d <= u32::MAX as f64 => FrameRate::new(n as u32, d as u32),
+                _ => None,
+            },
+            _ => None,
+        });
+        Ok(match sensor_rate {
+            Some(rate) if (rate.as_f64() - fps as f64).abs() < 0.0005 * rate.as_f64() => rate,
+            _ => snapped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_rate_snapping() {
+        assert_eq!(FrameRate::from_f32(23.976), Some(FrameRate::FPS_23_976));
+        assert_eq!(FrameRate::from_f32(24000.0 / 1001.0), Some(FrameRate::FPS_23_976));
+        assert_eq!(FrameRate::from_f32(29.97), Some(FrameRate::FPS_29_97));
+        assert_eq!(FrameRate::from_f32(59.94), Some(FrameRate::FPS_59_94));
+        assert_eq!(FrameRate::from_f32(119.88), FrameRate::new(120000, 1001));
+        assert_eq!(FrameRate::from_f32(25.0), Some(FrameRate::FPS_25));
+        assert_eq!(FrameRate::from_f32(12.5), FrameRate::new(25, 2));
+        assert_eq!(FrameRate::from_f32(0.0), None);
+        assert_eq!(FrameRate::new(48000, 2000), Some(FrameRate::FPS_24));
+        assert_eq!(FrameRate::FPS_29_97.nominal(), 30);
+        assert_eq!(FrameRate::FPS_29_97.to_string(), "30000/1001");
+    }
+
+    #[test]
+    fn frame_rate_conversions() {
+        let rate = FrameRate::FPS_23_976;
+        // An hour of 23.976 fps video is 86,313.6 frames, so the exact rate matters.
+        assert_eq!(rate.frame_to_time(86400), Duration::new(3603, 600_000_000));
+        assert_eq!(rate.time_to_frame(Duration::new(3603, 600_000_000)), 86400);
+        assert_eq!(rate.time_to_frame(Duration::new(3603, 599_999_999)), 86399);
+        assert_eq!(rate.frame_to_sample(1, 48000), Some(2002));
+        assert_eq!(rate.frame_to_sample(86400, 48000), Some(172_972_800));
+        assert_eq!(rate.sample_to_frame(2001, 48000), Some(0));
+        assert_eq!(rate.sample_to_frame(2002, 48000), Some(1));
+
+        // At 29.97 fps, frames don't start on whole samples.
+        let rate = FrameRate::FPS_29_97;
+        assert_eq!(rate.frame_to_time(1), Duration::new(0, 33_366_667));
+        for frame in 0..100_000 {
+            assert_eq!(rate.time_to_frame(rate.frame_to_time(frame)), frame);
+            assert_eq!(rate.sample_to_frame(rate.frame_to_sample(frame, 48000).unwrap(), 48000), Some(frame));
+        }
+        assert_eq!(rate.frame_to_sample(1, 48000), Some(1602));
+        assert_eq!(rate.sample_to_frame(1601, 48000), Some(0));
+        assert_eq!(rate.sample_to_frame(1602, 48000), Some(1));
+        assert_eq!(rate.frame_to_sample(1, 0), None);
+        assert_eq!(rate.sample_to_frame(1, 0), None);
+    }
+}
@@ -0,0 +1,294 @@
+//! Futures for SDK jobs. They're completed by the callback trampolines through per-job handlers, so
+//! they work with any executor and never need `Codec::flush_jobs`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use super::*;
+
+struct JobState<T> {
+    result: Option<Result<T, Error>>,
+    completed: bool,
+    waker: Option<Waker>,
+}
+
+/// Completes a `JobFuture`. If it's dropped without completing, for example because the SDK
+/// abandoned the job, the future resolves with `ErrorCode::Abort`.
+struct Completer<T> {
+    state: Arc<Mutex<JobState<T>>>,
+    operation: &'static str,
+}
+
+impl<T> Completer<T> {
+    fn complete(&self, result: Result<T, Error>) {
+        let waker = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.completed {
+                return;
+            }
+            state.completed = true;
+            state.result = Some(result);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        self.complete(Err(Error::new(ErrorCode::Abort, self.operation)));
+    }
+}
+
+/// A job that resolves once the SDK completes it. Dropping the future before then aborts the job.
+pub struct JobFuture<T> {
+    job: Option<Job>,
+    state: Arc<Mutex<JobState<T>>>,
+}
+
+impl<T> JobFuture<T> {
+    fn new(operation: &'static str) -> (JobFuture<T>, Completer<T>) {
+        let state = Arc::new(Mutex::new(JobState{
+            result: None,
+            completed: false,
+            waker: None,
+        }));
+        let future = JobFuture{
+            job: None,
+            state: state.clone(),
+        };
+        (future, Completer{
+            state: state,
+            operation: operation,
+        })
+    }
+
+    /// Takes ownership of the job once it's been created and submitted, or resolves the future with
+    /// the error that prevented it.
+    fn start(mut self, job: Result<Job, Error>) -> Self {
+        match job {
+            Ok(job) => self.job = Some(job),
+            Err(e) => {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                state.completed = true;
+                state.result = Some(Err(e));
+            },
+        }
+        self
+    }
+
+    fn is_completed(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).completed
+    }
+
+    /// Requests that the job be cancelled. The future still resolves, typically with
+    /// `ErrorCode::Abort`.
+    pub fn abort(&mut self) -> Result<(), Error> {
+        if self.is_completed() {
+            return Ok(());
+        }
+        match self.job {
+            Some(ref mut job) => job.abort(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Future for JobFuture<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None if state.completed => panic!("JobFuture polled after completion"),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<T> Drop for JobFuture<T> {
+    fn drop(&mut self) {
+        let _ = self.abort();
+    }
+}
+
+fn submit(job: Result<Job, Error>) -> Result<Job, Error> {
+    let mut job = job?;
+    job.submit()?;
+    Ok(job)
+}
+
+impl Clip {
+    /// Reads a frame. Equivalent to `create_job_read_frame`, but completes the returned future
+    /// instead of calling the codec's callback.
+    pub fn read_frame(&mut self, frame: u64) -> JobFuture<Frame> {
+        let (future, completer) = JobFuture::new("IBlackmagicRawCallback::ReadComplete");
+        let job = self.create_job_read_frame_with(frame, move |_job, result| completer.complete(result));
+        future.start(submit(job))
+    }
+
+    /// Trims the clip into a new file, as with `create_job_trim`. Progress is available via
+    /// `TrimFuture::progress`, and if the job fails or is aborted the partial file is removed.
+    pub fn trim<P: AsRef<Path>>(&mut self, path: P, frame_index: u64, frame_count: u64, options: TrimOptions) -> TrimFuture {
+        let (future, completer) = JobFuture::new("IBlackmagicRawCallback::TrimComplete");
+        let progress = Arc::new(Mutex::new(ProgressState{
+            latest: None,
+            finished: false,
+            waker: None,
+        }));
+        let finisher = ProgressFinisher{
+            state: progress.clone(),
+        };
+        let progress_state = progress.clone();
+        let job = self.create_job_trim_with(path, frame_index, frame_count, options, move |_job, progress| {
+            let waker = {
+                let mut state = progress_state.lock().unwrap_or_else(|e| e.into_inner());
+                state.latest = Some(progress);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }, move |_job, result| {
+            completer.complete(result);
+            drop(finisher);
+        });
+        TrimFuture{
+            inner: future.start(submit(job)),
+            progress: progress,
+        }
+    }
+}
+
+impl Frame {
+    /// Decodes and processes the frame. Equivalent to `create_job_decode_and_process_frame`, but
+    /// completes the returned future instead of calling the codec's callback.
+    pub fn decode_and_process(&mut self, clip_processing_attributes: Option<ClipProcessingAttributes>, frame_processing_attributes: Option<FrameProcessingAttributes>) -> JobFuture<ProcessedImage> {
+        let (future, completer) = JobFuture::new("IBlackmagicRawCallback::ProcessComplete");
+        let job = self.create_job_decode_and_process_frame_with(clip_processing_attributes, frame_processing_attributes, move |_job, result| completer.complete(result));
+        future.start(submit(job))
+    }
+}
+
+struct ProgressState {
+    latest: Option<f32>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+/// Ends the progress stream when the trim job's handlers are dropped.
+struct ProgressFinisher {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl Drop for ProgressFinisher {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.finished = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// A trim job that resolves once the new file has been written.
+pub struct TrimFuture {
+    inner: JobFuture<()>,
+    progress: Arc<Mutex<ProgressState>>,
+}
+
+impl TrimFuture {
+    /// Returns a stream of the job's progress, from 0.0 to 1.0, that ends when the job completes.
+    /// Only the most recent value is kept, so a slow consumer sees fewer updates rather than a
+    /// backlog.
+    pub fn progress(&self) -> TrimProgress {
+        TrimProgress{
+            state: self.progress.clone(),
+        }
+    }
+
+    /// Requests that the job be cancelled. The future still resolves, typically with
+    /// `ErrorCode::Abort`.
+    pub fn abort(&mut self) -> Result<(), Error> {
+        self.inner.abort()
+    }
+}
+
+impl Future for TrimFuture {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.inner).poll(cx)
+    }
+}
+
+/// The progress of a trim job. See `TrimFuture::progress`.
+pub struct TrimProgress {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl Stream for TrimProgress {
+    type Item = f32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<f32>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(progress) = state.latest.take() {
+            Poll::Ready(Some(progress))
+        } else if state.finished {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::{RawWaker, RawWakerVTable};
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        unsafe { Waker::from_raw(clone(std::ptr::null())) }
+    }
+
+    #[test]
+    fn job_future_resolves_when_completed_or_abandoned() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let (mut future, completer) = JobFuture::<u32>::new("IBlackmagicRawCallback::ReadComplete");
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        completer.complete(Ok(7));
+        drop(completer);
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Ready(Ok(7)) => {},
+            _ => panic!("expected the completed value"),
+        }
+
+        let (mut future, completer) = JobFuture::<u32>::new("IBlackmagicRawCallback::ReadComplete");
+        drop(completer);
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Ready(Err(e)) => assert_eq!(e.code(), Some(ErrorCode::Abort)),
+            _ => panic!("expected an abort"),
+        }
+    }
+}
@@ -53,7 +53,8 @@ struct StringArg {
     CFStringRef _temp;
 #else
     ~StringArg() {
-        *_dest = new Buffer(_temp);
+        // On Linux the returned string is owned by the SDK object, so we need our own copy.
+        *_dest = _temp == nullptr ? nullptr : CopyString(_temp);
     }
 
     operator const char**() {
@@ -117,14 +118,96 @@ HRESULT blackmagic_raw_unknown_query_interface(IUnknown* obj, REFIID iid, LPVOID
     return obj->QueryInterface(iid, iface);
 }
 
+IBlackmagicRawFactory* create_blackmagic_raw_factory_instance() {
+    return CreateBlackmagicRawFactoryInstance();
+}
+
 IBlackmagicRawFactory* create_blackmagic_raw_factory_instance_from_path(const char* path) {
     return CreateBlackmagicRawFactoryInstanceFromPath(CStringToString(path));
 }
 
+IBlackmagicRawFactory* create_blackmagic_raw_factory_instance_from_exe_relative_path(const char* path) {
+    return CreateBlackmagicRawFactoryInstanceFromExeRelativePath(CStringToString(path));
+}
+
 HRESULT blackmagic_raw_factory_create_codec(IBlackmagicRawFactory* factory, IBlackmagicRaw** out) {
     return factory->CreateCodec(out);
 }
 
+HRESULT blackmagic_raw_factory_create_pipeline_iterator(IBlackmagicRawFactory* factory, BlackmagicRawInterop interop, IBlackmagicRawPipelineIterator** out) {
+    return factory->CreatePipelineIterator(interop, out);
+}
+
+HRESULT blackmagic_raw_factory_create_pipeline_device_iterator(IBlackmagicRawFactory* factory, BlackmagicRawPipeline pipeline, BlackmagicRawInterop interop, IBlackmagicRawPipelineDeviceIterator** out) {
+    return factory->CreatePipelineDeviceIterator(pipeline, interop, out);
+}
+
+HRESULT blackmagic_raw_pipeline_iterator_next(IBlackmagicRawPipelineIterator* it) {
+    return it->Next();
+}
+
+HRESULT blackmagic_raw_pipeline_iterator_get_name(IBlackmagicRawPipelineIterator* it, Buffer** out) {
+    return it->GetName(StringArg(out));
+}
+
+HRESULT blackmagic_raw_pipeline_iterator_get_interop(IBlackmagicRawPipelineIterator* it, BlackmagicRawInterop* out) {
+    return it->GetInterop(out);
+}
+
+HRESULT blackmagic_raw_pipeline_iterator_get_pipeline(IBlackmagicRawPipelineIterator* it, BlackmagicRawPipeline* out) {
+    return it->GetPipeline(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_iterator_next(IBlackmagicRawPipelineDeviceIterator* it) {
+    return it->Next();
+}
+
+HRESULT blackmagic_raw_pipeline_device_iterator_get_pipeline(IBlackmagicRawPipelineDeviceIterator* it, BlackmagicRawPipeline* out) {
+    return it->GetPipeline(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_iterator_get_interop(IBlackmagicRawPipelineDeviceIterator* it, BlackmagicRawInterop* out) {
+    return it->GetInterop(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_iterator_create_device(IBlackmagicRawPipelineDeviceIterator* it, IBlackmagicRawPipelineDevice** out) {
+    return it->CreateDevice(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_set_best_instruction_set(IBlackmagicRawPipelineDevice* device) {
+    return device->SetBestInstructionSet();
+}
+
+HRESULT blackmagic_raw_pipeline_device_set_instruction_set(IBlackmagicRawPipelineDevice* device, BlackmagicRawInstructionSet instructionSet) {
+    return device->SetInstructionSet(instructionSet);
+}
+
+HRESULT blackmagic_raw_pipeline_device_get_instruction_set(IBlackmagicRawPipelineDevice* device, BlackmagicRawInstructionSet* out) {
+    return device->GetInstructionSet(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_get_index(IBlackmagicRawPipelineDevice* device, uint32_t* out) {
+    return device->GetIndex(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_get_name(IBlackmagicRawPipelineDevice* device, Buffer** out) {
+    return device->GetName(StringArg(out));
+}
+
+HRESULT blackmagic_raw_pipeline_device_get_interop(IBlackmagicRawPipelineDevice* device, BlackmagicRawInterop* out) {
+    return device->GetInterop(out);
+}
+
+HRESULT blackmagic_raw_pipeline_device_get_pipeline(IBlackmagicRawPipelineDevice* device, BlackmagicRawPipeline* out) {
+    void* context = nullptr;
+    void* commandQueue = nullptr;
+    return device->GetPipeline(out, &context, &commandQueue);
+}
+
+HRESULT blackmagic_raw_pipeline_device_get_pipeline_name(IBlackmagicRawPipelineDevice* device, Buffer** out) {
+    return device->GetPipelineName(StringArg(out));
+}
+
 HRESULT blackmagic_raw_open_clip(IBlackmagicRaw* codec, const char* fileName, IBlackmagicRawClip** out) {
     return codec->OpenClip(CStringToString(fileName), out);
 }
@@ -137,6 +220,58 @@ HRESULT blackmagic_raw_flush_jobs(IBlackmagicRaw* codec) {
     return codec->FlushJobs();
 }
 
+HRESULT blackmagic_raw_configuration_set_pipeline(IBlackmagicRawConfiguration* config, BlackmagicRawPipeline pipeline) {
+    return config->SetPipeline(pipeline, nullptr, nullptr);
+}
+
+HRESULT blackmagic_raw_configuration_get_pipeline(IBlackmagicRawConfiguration* config, BlackmagicRawPipeline* out) {
+    return config->GetPipeline(out, nullptr, nullptr);
+}
+
+HRESULT blackmagic_raw_configuration_is_pipeline_supported(IBlackmagicRawConfiguration* config, BlackmagicRawPipeline pipeline, bool* out) {
+    return config->IsPipelineSupported(pipeline, out);
+}
+
+HRESULT blackmagic_raw_configuration_set_cpu_threads(IBlackmagicRawConfiguration* config, uint32_t threadCount) {
+    return config->SetCPUThreads(threadCount);
+}
+
+HRESULT blackmagic_raw_configuration_get_cpu_threads(IBlackmagicRawConfiguration* config, uint32_t* out) {
+    return config->GetCPUThreads(out);
+}
+
+HRESULT blackmagic_raw_configuration_get_max_cpu_thread_count(IBlackmagicRawConfiguration* config, uint32_t* out) {
+    return config->GetMaxCPUThreadCount(out);
+}
+
+HRESULT blackmagic_raw_configuration_set_write_metadata_per_frame(IBlackmagicRawConfiguration* config, bool writePerFrame) {
+    return config->SetWriteMetadataPerFrame(writePerFrame);
+}
+
+HRESULT blackmagic_raw_configuration_get_write_metadata_per_frame(IBlackmagicRawConfiguration* config, bool* out) {
+    return config->GetWriteMetadataPerFrame(out);
+}
+
+HRESULT blackmagic_raw_configuration_set_from_device(IBlackmagicRawConfiguration* config, IBlackmagicRawPipelineDevice* device) {
+    return config->SetFromDevice(device);
+}
+
+HRESULT blackmagic_raw_configuration_ex_get_resource_manager(IBlackmagicRawConfigurationEx* config, IBlackmagicRawResourceManager** out) {
+    return config->GetResourceManager(out);
+}
+
+HRESULT blackmagic_raw_configuration_ex_set_resource_manager(IBlackmagicRawConfigurationEx* config, IBlackmagicRawResourceManager* resourceManager) {
+    return config->SetResourceManager(resourceManager);
+}
+
+HRESULT blackmagic_raw_configuration_ex_get_instruction_set(IBlackmagicRawConfigurationEx* config, BlackmagicRawInstructionSet* out) {
+    return config->GetInstructionSet(out);
+}
+
+HRESULT blackmagic_raw_configuration_ex_set_instruction_set(IBlackmagicRawConfigurationEx* config, BlackmagicRawInstructionSet instructionSet) {
+    return config->SetInstructionSet(instructionSet);
+}
+
 HRESULT blackmagic_raw_clip_get_width(IBlackmagicRawClip* clip, uint32_t *out) {
     return clip->GetWidth(out);
 }
@@ -157,6 +292,42 @@ HRESULT blackmagic_raw_clip_get_metadata_iterator(IBlackmagicRawClip* clip, IBla
     return clip->GetMetadataIterator(iterator);
 }
 
+HRESULT blackmagic_raw_clip_get_camera_type(IBlackmagicRawClip* clip, Buffer** out) {
+    return clip->GetCameraType(StringArg(out));
+}
+
+HRESULT blackmagic_raw_clip_get_timecode_for_frame(IBlackmagicRawClip* clip, uint64_t frameIndex, Buffer** out) {
+    return clip->GetTimecodeForFrame(frameIndex, StringArg(out));
+}
+
+HRESULT blackmagic_raw_clip_get_metadata(IBlackmagicRawClip* clip, const char* key, Variant* value) {
+    return clip->GetMetadata(CStringToString(key), value);
+}
+
+HRESULT blackmagic_raw_clip_set_metadata(IBlackmagicRawClip* clip, const char* key, Variant* value) {
+    return clip->SetMetadata(CStringToString(key), value);
+}
+
+HRESULT blackmagic_raw_clip_get_sidecar_file_attached(IBlackmagicRawClip* clip, bool* out) {
+    return clip->GetSidecarFileAttached(out);
+}
+
+HRESULT blackmagic_raw_clip_save_sidecar_file(IBlackmagicRawClip* clip) {
+    return clip->SaveSidecarFile();
+}
+
+HRESULT blackmagic_raw_clip_reload_sidecar_file(IBlackmagicRawClip* clip) {
+    return clip->ReloadSidecarFile();
+}
+
+HRESULT blackmagic_raw_clip_ex_query_timecode_info(IBlackmagicRawClipEx* clip, uint32_t* baseFrameIndex, bool* isDropFrameTimecode) {
+    return clip->QueryTimecodeInfo(baseFrameIndex, isDropFrameTimecode);
+}
+
+HRESULT blackmagic_raw_clip_clone_clip_processing_attributes(IBlackmagicRawClip* clip, IBlackmagicRawClipProcessingAttributes** out) {
+    return clip->CloneClipProcessingAttributes(out);
+}
+
 HRESULT blackmagic_raw_clip_create_job_read_frame(IBlackmagicRawClip* clip, uint64_t frameIndex, IBlackmagicRawJob** job) {
     return clip->CreateJobReadFrame(frameIndex, job);
 }
@@ -193,14 +364,130 @@ HRESULT blackmagic_raw_job_submit(IBlackmagicRawJob* job) {
     return job->Submit();
 }
 
+HRESULT blackmagic_raw_job_abort(IBlackmagicRawJob* job) {
+    return job->Abort();
+}
+
+HRESULT blackmagic_raw_job_set_user_data(IBlackmagicRawJob* job, void* userData) {
+    return job->SetUserData(userData);
+}
+
+HRESULT blackmagic_raw_job_get_user_data(IBlackmagicRawJob* job, void** userData) {
+    return job->GetUserData(userData);
+}
+
+HRESULT blackmagic_raw_frame_get_frame_index(IBlackmagicRawFrame* frame, uint64_t* out) {
+    return frame->GetFrameIndex(out);
+}
+
+HRESULT blackmagic_raw_frame_get_timecode(IBlackmagicRawFrame* frame, Buffer** out) {
+    return frame->GetTimecode(StringArg(out));
+}
+
+HRESULT blackmagic_raw_frame_get_metadata_iterator(IBlackmagicRawFrame* frame, IBlackmagicRawMetadataIterator** iterator) {
+    return frame->GetMetadataIterator(iterator);
+}
+
+HRESULT blackmagic_raw_frame_get_metadata(IBlackmagicRawFrame* frame, const char* key, Variant* value) {
+    return frame->GetMetadata(CStringToString(key), value);
+}
+
+HRESULT blackmagic_raw_frame_set_metadata(IBlackmagicRawFrame* frame, const char* key, Variant* value) {
+    return frame->SetMetadata(CStringToString(key), value);
+}
+
+HRESULT blackmagic_raw_frame_clone_frame_processing_attributes(IBlackmagicRawFrame* frame, IBlackmagicRawFrameProcessingAttributes** out) {
+    return frame->CloneFrameProcessingAttributes(out);
+}
+
 HRESULT blackmagic_raw_frame_set_resource_format(IBlackmagicRawFrame* frame, BlackmagicRawResourceFormat format) {
     return frame->SetResourceFormat(format);
 }
 
+HRESULT blackmagic_raw_frame_get_resource_format(IBlackmagicRawFrame* frame, BlackmagicRawResourceFormat* out) {
+    return frame->GetResourceFormat(out);
+}
+
+HRESULT blackmagic_raw_frame_set_resolution_scale(IBlackmagicRawFrame* frame, BlackmagicRawResolutionScale scale) {
+    return frame->SetResolutionScale(scale);
+}
+
+HRESULT blackmagic_raw_frame_get_resolution_scale(IBlackmagicRawFrame* frame, BlackmagicRawResolutionScale* out) {
+    return frame->GetResolutionScale(out);
+}
+
 HRESULT blackmagic_raw_frame_create_job_decode_and_process_frame(IBlackmagicRawFrame* frame, IBlackmagicRawClipProcessingAttributes* clipProcessingAttributes, IBlackmagicRawFrameProcessingAttributes* frameProcessingAttributes, IBlackmagicRawJob** job) {
     return frame->CreateJobDecodeAndProcessFrame(clipProcessingAttributes, frameProcessingAttributes, job);
 }
 
+HRESULT blackmagic_raw_clip_processing_attributes_get_clip_attribute(IBlackmagicRawClipProcessingAttributes* attributes, BlackmagicRawClipProcessingAttribute attribute, Variant* value) {
+    return attributes->GetClipAttribute(attribute, value);
+}
+
+HRESULT blackmagic_raw_clip_processing_attributes_set_clip_attribute(IBlackmagicRawClipProcessingAttributes* attributes, BlackmagicRawClipProcessingAttribute attribute, Variant* value) {
+    return attributes->SetClipAttribute(attribute, value);
+}
+
+HRESULT blackmagic_raw_clip_processing_attributes_get_post_3d_lut(IBlackmagicRawClipProcessingAttributes* attributes, IBlackmagicRawPost3DLUT** lut) {
+    return attributes->GetPost3DLUT(lut);
+}
+
+HRESULT blackmagic_raw_post_3d_lut_get_name(IBlackmagicRawPost3DLUT* lut, Buffer** out) {
+    return lut->GetName(StringArg(out));
+}
+
+HRESULT blackmagic_raw_post_3d_lut_get_title(IBlackmagicRawPost3DLUT* lut, Buffer** out) {
+    return lut->GetTitle(StringArg(out));
+}
+
+HRESULT blackmagic_raw_post_3d_lut_get_size(IBlackmagicRawPost3DLUT* lut, uint32_t* out) {
+    return lut->GetSize(out);
+}
+
+HRESULT blackmagic_raw_post_3d_lut_get_resource_cpu(IBlackmagicRawPost3DLUT* lut, void** out) {
+    return lut->GetResourceCPU(out);
+}
+
+HRESULT blackmagic_raw_post_3d_lut_get_resource_size_bytes(IBlackmagicRawPost3DLUT* lut, uint32_t* out) {
+    return lut->GetResourceSizeBytes(out);
+}
+
+HRESULT blackmagic_raw_frame_processing_attributes_get_frame_attribute(IBlackmagicRawFrameProcessingAttributes* attributes, BlackmagicRawFrameProcessingAttribute attribute, Variant* value) {
+    return attributes->GetFrameAttribute(attribute, value);
+}
+
+HRESULT blackmagic_raw_frame_processing_attributes_set_frame_attribute(IBlackmagicRawFrameProcessingAttributes* attributes, BlackmagicRawFrameProcessingAttribute attribute, Variant* value) {
+    return attributes->SetFrameAttribute(attribute, value);
+}
+
+HRESULT blackmagic_raw_constants_get_clip_processing_attribute_range(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawClipProcessingAttribute attribute, Variant* valueMin, Variant* valueMax) {
+    return constants->GetClipProcessingAttributeRange(CStringToString(cameraType), attribute, valueMin, valueMax);
+}
+
+HRESULT blackmagic_raw_constants_get_clip_processing_attribute_list(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawClipProcessingAttribute attribute, Variant* array, uint32_t* arrayElementCount) {
+    return constants->GetClipProcessingAttributeList(CStringToString(cameraType), attribute, array, arrayElementCount);
+}
+
+HRESULT blackmagic_raw_constants_get_frame_processing_attribute_range(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawFrameProcessingAttribute attribute, Variant* valueMin, Variant* valueMax) {
+    return constants->GetFrameProcessingAttributeRange(CStringToString(cameraType), attribute, valueMin, valueMax);
+}
+
+HRESULT blackmagic_raw_constants_get_frame_processing_attribute_list(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawFrameProcessingAttribute attribute, Variant* array, uint32_t* arrayElementCount) {
+    return constants->GetFrameProcessingAttributeList(CStringToString(cameraType), attribute, array, arrayElementCount);
+}
+
+HRESULT blackmagic_raw_constants_get_iso_list_for_analog_gain(IBlackmagicRawConstants* constants, const char* cameraType, float analogGain, uint16_t* array, uint32_t* arrayElementCount) {
+    return constants->GetISOListForAnalogGain(CStringToString(cameraType), analogGain, array, arrayElementCount);
+}
+
+HRESULT blackmagic_raw_tone_curve_get_tone_curve(IBlackmagicRawToneCurve* toneCurve, const char* cameraType, const char* gamma, uint16_t gen, float* contrast, float* saturation, float* midpoint, float* highlights, float* shadows, float* blackLevel, float* whiteLevel, uint16_t* videoBlackLevel) {
+    return toneCurve->GetToneCurve(CStringToString(cameraType), CStringToString(gamma), gen, contrast, saturation, midpoint, highlights, shadows, blackLevel, whiteLevel, videoBlackLevel);
+}
+
+HRESULT blackmagic_raw_tone_curve_evaluate_tone_curve(IBlackmagicRawToneCurve* toneCurve, const char* cameraType, uint16_t gen, float contrast, float saturation, float midpoint, float highlights, float shadows, float blackLevel, float whiteLevel, uint16_t videoBlackLevel, float* array, uint32_t arrayElementCount) {
+    return toneCurve->EvaluateToneCurve(CStringToString(cameraType), gen, contrast, saturation, midpoint, highlights, shadows, blackLevel, whiteLevel, videoBlackLevel, array, arrayElementCount);
+}
+
 HRESULT blackmagic_raw_processed_image_get_width(IBlackmagicRawProcessedImage* img, uint32_t* out) {
     return img->GetWidth(out);
 }
@@ -217,6 +504,14 @@ HRESULT blackmagic_raw_processed_image_get_resource(IBlackmagicRawProcessedImage
     return img->GetResource(bytes);
 }
 
+HRESULT blackmagic_raw_processed_image_get_resource_type(IBlackmagicRawProcessedImage* img, BlackmagicRawResourceType* out) {
+    return img->GetResourceType(out);
+}
+
+HRESULT blackmagic_raw_processed_image_get_resource_format(IBlackmagicRawProcessedImage* img, BlackmagicRawResourceFormat* out) {
+    return img->GetResourceFormat(out);
+}
+
 extern void callback_read_complete(void* impl, IBlackmagicRawJob* job, HRESULT result, IBlackmagicRawFrame* frame);
 extern void callback_decode_complete(void* impl, IBlackmagicRawJob* job, HRESULT result);
 extern void callback_process_complete(void* impl, IBlackmagicRawJob* job, HRESULT result, IBlackmagicRawProcessedImage* processedImage);
@@ -225,10 +520,13 @@ extern void callback_trim_complete(void* impl, IBlackmagicRawJob* job, HRESULT r
 extern void callback_sidecar_metadata_parse_warning(void* impl, IBlackmagicRawClip* clip, const char* fileName, uint32_t lineNumber, const char* info);
 extern void callback_sidecar_metadata_parse_error(void* impl, IBlackmagicRawClip* clip, const char* fileName, uint32_t lineNumber, const char* info);
 extern void callback_prepare_pipeline_complete(void* impl, void* userData, HRESULT result);
+extern void callback_drop(void* impl);
 
 struct Callback: IBlackmagicRawCallback {
     explicit Callback(void* implementation) : _ref_count(1), _implementation(implementation) {}
-    virtual ~Callback() {}
+    virtual ~Callback() {
+        callback_drop(_implementation);
+    }
 
     virtual void ReadComplete(IBlackmagicRawJob* job, HRESULT result, IBlackmagicRawFrame* frame) {
         callback_read_complete(_implementation, job, result, frame);
@@ -285,11 +583,11 @@ struct Callback: IBlackmagicRawCallback {
     }
 
     virtual ULONG AddRef() {
-        return _ref_count.fetch_add(1);
+        return _ref_count.fetch_add(1) + 1;
     }
 
     virtual ULONG Release() {
-        int refs = _ref_count.fetch_sub(1);
+        uint32_t refs = _ref_count.fetch_sub(1) - 1;
         if (refs == 0) {
             delete this;
         }
@@ -304,6 +602,76 @@ IBlackmagicRawCallback* create_blackmagic_raw_callback(void* implementation) {
     return new Callback(implementation);
 }
 
+extern HRESULT resource_manager_create_resource(void* impl, uint32_t sizeBytes, BlackmagicRawResourceType type, BlackmagicRawResourceUsage usage, void** resource);
+extern HRESULT resource_manager_release_resource(void* impl, void* resource, BlackmagicRawResourceType type);
+extern HRESULT resource_manager_copy_resource(void* impl, void* source, BlackmagicRawResourceType sourceType, void* destination, BlackmagicRawResourceType destinationType, uint32_t sizeBytes, bool copyAsync);
+extern HRESULT resource_manager_get_resource_host_pointer(void* impl, void* resource, BlackmagicRawResourceType type, void** hostPointer);
+extern void resource_manager_drop(void* impl);
+
+struct ResourceManager: IBlackmagicRawResourceManager {
+    explicit ResourceManager(void* implementation) : _ref_count(1), _implementation(implementation) {}
+    virtual ~ResourceManager() {
+        resource_manager_drop(_implementation);
+    }
+
+    virtual HRESULT CreateResource(void* /* context */, void* /* commandQueue */, uint32_t sizeBytes, BlackmagicRawResourceType type, BlackmagicRawResourceUsage usage, void** resource) {
+        return resource_manager_create_resource(_implementation, sizeBytes, type, usage, resource);
+    }
+
+    virtual HRESULT ReleaseResource(void* /* context */, void* /* commandQueue */, void* resource, BlackmagicRawResourceType type) {
+        return resource_manager_release_resource(_implementation, resource, type);
+    }
+
+    virtual HRESULT CopyResource(void* /* context */, void* /* commandQueue */, void* source, BlackmagicRawResourceType sourceType, void* destination, BlackmagicRawResourceType destinationType, uint32_t sizeBytes, bool copyAsync) {
+        return resource_manager_copy_resource(_implementation, source, sourceType, destination, destinationType, sizeBytes, copyAsync);
+    }
+
+    virtual HRESULT GetResourceHostPointer(void* /* context */, void* /* commandQueue */, void* resource, BlackmagicRawResourceType resourceType, void** hostPointer) {
+        return resource_manager_get_resource_host_pointer(_implementation, resource, resourceType, hostPointer);
+    }
+
+    virtual HRESULT QueryInterface(REFIID iid, LPVOID *ppv) {
+        if (ppv == NULL) {
+            return E_INVALIDARG;
+        }
+
+        *ppv = NULL;
+
+        CFUUIDBytes iunknown = CFUUIDGetUUIDBytes(IUnknownUUID);
+        HRESULT result = E_NOINTERFACE;
+        if (memcmp(&iid, &iunknown, sizeof(REFIID)) == 0) {
+            *ppv = this;
+            AddRef();
+            result = S_OK;
+        } else if (memcmp(&iid, &IID_IBlackmagicRawResourceManager, sizeof(REFIID)) == 0) {
+            *ppv = (IBlackmagicRawResourceManager*)this;
+            AddRef();
+            result = S_OK;
+        }
+
+        return result;
+    }
+
+    virtual ULONG AddRef() {
+        return _ref_count.fetch_add(1) + 1;
+    }
+
+    virtual ULONG Release() {
+        uint32_t refs = _ref_count.fetch_sub(1) - 1;
+        if (refs == 0) {
+            delete this;
+        }
+        return refs;
+    }
+
+    std::atomic<uint32_t> _ref_count;
+    void* _implementation;
+};
+
+IBlackmagicRawResourceManager* create_blackmagic_raw_resource_manager(void* implementation) {
+    return new ResourceManager(implementation);
+}
+
 const void* buffer_data(Buffer* buf) {
     return buf->_data;
 }
@@ -318,4 +686,18 @@ void blackmagic_raw_variant_get_string(Variant* v, Buffer** out) {
     *out = CopyString(v->bstrVal);
 }
 
+// On Linux the variant borrows the given string, so it must outlive the variant.
+void blackmagic_raw_variant_set_string(Variant* v, const char* s) {
+    v->vt = blackmagicRawVariantTypeString;
+    v->bstrVal = CStringToString(s);
+}
+
+void blackmagic_raw_variant_release_string(Variant* v) {
+#ifdef __APPLE__
+    CFRelease(v->bstrVal);
+#endif
+    v->bstrVal = nullptr;
+    v->vt = blackmagicRawVariantTypeEmpty;
+}
+
 }
@@ -8,19 +8,66 @@ ULONG blackmagic_raw_unknown_add_ref(IUnknown* obj);
 ULONG blackmagic_raw_unknown_release(IUnknown* obj);
 HRESULT blackmagic_raw_unknown_query_interface(IUnknown* obj, REFIID iid, LPVOID* iface);
 
+IBlackmagicRawFactory* create_blackmagic_raw_factory_instance();
 IBlackmagicRawFactory* create_blackmagic_raw_factory_instance_from_path(const char* path);
+IBlackmagicRawFactory* create_blackmagic_raw_factory_instance_from_exe_relative_path(const char* path);
 HRESULT blackmagic_raw_factory_create_codec(IBlackmagicRawFactory* factory, IBlackmagicRaw** out);
+HRESULT blackmagic_raw_factory_create_pipeline_iterator(IBlackmagicRawFactory* factory, BlackmagicRawInterop interop, IBlackmagicRawPipelineIterator** out);
+HRESULT blackmagic_raw_factory_create_pipeline_device_iterator(IBlackmagicRawFactory* factory, BlackmagicRawPipeline pipeline, BlackmagicRawInterop interop, IBlackmagicRawPipelineDeviceIterator** out);
+
+HRESULT blackmagic_raw_pipeline_iterator_next(IBlackmagicRawPipelineIterator* it);
+HRESULT blackmagic_raw_pipeline_iterator_get_name(IBlackmagicRawPipelineIterator* it, Buffer** out);
+HRESULT blackmagic_raw_pipeline_iterator_get_interop(IBlackmagicRawPipelineIterator* it, BlackmagicRawInterop* out);
+HRESULT blackmagic_raw_pipeline_iterator_get_pipeline(IBlackmagicRawPipelineIterator* it, BlackmagicRawPipeline* out);
+
+HRESULT blackmagic_raw_pipeline_device_iterator_next(IBlackmagicRawPipelineDeviceIterator* it);
+HRESULT blackmagic_raw_pipeline_device_iterator_get_pipeline(IBlackmagicRawPipelineDeviceIterator* it, BlackmagicRawPipeline* out);
+HRESULT blackmagic_raw_pipeline_device_iterator_get_interop(IBlackmagicRawPipelineDeviceIterator* it, BlackmagicRawInterop* out);
+HRESULT blackmagic_raw_pipeline_device_iterator_create_device(IBlackmagicRawPipelineDeviceIterator* it, IBlackmagicRawPipelineDevice** out);
+
+HRESULT blackmagic_raw_pipeline_device_set_best_instruction_set(IBlackmagicRawPipelineDevice* device);
+HRESULT blackmagic_raw_pipeline_device_set_instruction_set(IBlackmagicRawPipelineDevice* device, BlackmagicRawInstructionSet instructionSet);
+HRESULT blackmagic_raw_pipeline_device_get_instruction_set(IBlackmagicRawPipelineDevice* device, BlackmagicRawInstructionSet* out);
+HRESULT blackmagic_raw_pipeline_device_get_index(IBlackmagicRawPipelineDevice* device, uint32_t* out);
+HRESULT blackmagic_raw_pipeline_device_get_name(IBlackmagicRawPipelineDevice* device, Buffer** out);
+HRESULT blackmagic_raw_pipeline_device_get_interop(IBlackmagicRawPipelineDevice* device, BlackmagicRawInterop* out);
+HRESULT blackmagic_raw_pipeline_device_get_pipeline(IBlackmagicRawPipelineDevice* device, BlackmagicRawPipeline* out);
+HRESULT blackmagic_raw_pipeline_device_get_pipeline_name(IBlackmagicRawPipelineDevice* device, Buffer** out);
 
 HRESULT blackmagic_raw_open_clip(IBlackmagicRaw* codec, const char* fileName, IBlackmagicRawClip** out);
 HRESULT blackmagic_raw_set_callback(IBlackmagicRaw* codec, IBlackmagicRawCallback* callback);
 HRESULT blackmagic_raw_flush_jobs(IBlackmagicRaw* codec);
 
+HRESULT blackmagic_raw_configuration_set_pipeline(IBlackmagicRawConfiguration* config, BlackmagicRawPipeline pipeline);
+HRESULT blackmagic_raw_configuration_get_pipeline(IBlackmagicRawConfiguration* config, BlackmagicRawPipeline* out);
+HRESULT blackmagic_raw_configuration_is_pipeline_supported(IBlackmagicRawConfiguration* config, BlackmagicRawPipeline pipeline, bool* out);
+HRESULT blackmagic_raw_configuration_set_cpu_threads(IBlackmagicRawConfiguration* config, uint32_t threadCount);
+HRESULT blackmagic_raw_configuration_get_cpu_threads(IBlackmagicRawConfiguration* config, uint32_t* out);
+HRESULT blackmagic_raw_configuration_get_max_cpu_thread_count(IBlackmagicRawConfiguration* config, uint32_t* out);
+HRESULT blackmagic_raw_configuration_set_write_metadata_per_frame(IBlackmagicRawConfiguration* config, bool writePerFrame);
+HRESULT blackmagic_raw_configuration_get_write_metadata_per_frame(IBlackmagicRawConfiguration* config, bool* out);
+HRESULT blackmagic_raw_configuration_set_from_device(IBlackmagicRawConfiguration* config, IBlackmagicRawPipelineDevice* device);
+
+HRESULT blackmagic_raw_configuration_ex_get_resource_manager(IBlackmagicRawConfigurationEx* config, IBlackmagicRawResourceManager** out);
+HRESULT blackmagic_raw_configuration_ex_set_resource_manager(IBlackmagicRawConfigurationEx* config, IBlackmagicRawResourceManager* resourceManager);
+HRESULT blackmagic_raw_configuration_ex_get_instruction_set(IBlackmagicRawConfigurationEx* config, BlackmagicRawInstructionSet* out);
+HRESULT blackmagic_raw_configuration_ex_set_instruction_set(IBlackmagicRawConfigurationEx* config, BlackmagicRawInstructionSet instructionSet);
+
 HRESULT blackmagic_raw_clip_get_width(IBlackmagicRawClip* clip, uint32_t *out);
 HRESULT blackmagic_raw_clip_get_height(IBlackmagicRawClip* clip, uint32_t *out);
 HRESULT blackmagic_raw_clip_get_frame_rate(IBlackmagicRawClip* clip, float *out);
 HRESULT blackmagic_raw_clip_get_frame_count(IBlackmagicRawClip* clip, uint64_t *out);
 HRESULT blackmagic_raw_clip_get_metadata_iterator(IBlackmagicRawClip* clip, IBlackmagicRawMetadataIterator** iterator);
 
+HRESULT blackmagic_raw_clip_get_camera_type(IBlackmagicRawClip* clip, Buffer** out);
+HRESULT blackmagic_raw_clip_get_timecode_for_frame(IBlackmagicRawClip* clip, uint64_t frameIndex, Buffer** out);
+HRESULT blackmagic_raw_clip_get_metadata(IBlackmagicRawClip* clip, const char* key, Variant* value);
+HRESULT blackmagic_raw_clip_set_metadata(IBlackmagicRawClip* clip, const char* key, Variant* value);
+HRESULT blackmagic_raw_clip_get_sidecar_file_attached(IBlackmagicRawClip* clip, bool* out);
+HRESULT blackmagic_raw_clip_save_sidecar_file(IBlackmagicRawClip* clip);
+HRESULT blackmagic_raw_clip_reload_sidecar_file(IBlackmagicRawClip* clip);
+HRESULT blackmagic_raw_clip_ex_query_timecode_info(IBlackmagicRawClipEx* clip, uint32_t* baseFrameIndex, bool* isDropFrameTimecode);
+HRESULT blackmagic_raw_clip_clone_clip_processing_attributes(IBlackmagicRawClip* clip, IBlackmagicRawClipProcessingAttributes** out);
 HRESULT blackmagic_raw_clip_create_job_read_frame(IBlackmagicRawClip* clip, uint64_t frameIndex, IBlackmagicRawJob** job);
 HRESULT blackmagic_raw_clip_create_job_trim(IBlackmagicRawClip* clip, const char* fileName, uint64_t frameIndex, uint64_t frameCount, IBlackmagicRawClipProcessingAttributes* clipProcessingAttributes, IBlackmagicRawFrameProcessingAttributes* frameProcessingAttributes, IBlackmagicRawJob** job);
 
@@ -33,20 +80,59 @@ HRESULT blackmagic_raw_metadata_iterator_get_key(IBlackmagicRawMetadataIterator*
 HRESULT blackmagic_raw_metadata_iterator_get_data(IBlackmagicRawMetadataIterator* it, Variant* data);
 
 HRESULT blackmagic_raw_job_submit(IBlackmagicRawJob* job);
-
+HRESULT blackmagic_raw_job_abort(IBlackmagicRawJob* job);
+HRESULT blackmagic_raw_job_set_user_data(IBlackmagicRawJob* job, void* userData);
+HRESULT blackmagic_raw_job_get_user_data(IBlackmagicRawJob* job, void** userData);
+
+HRESULT blackmagic_raw_frame_get_frame_index(IBlackmagicRawFrame* frame, uint64_t* out);
+HRESULT blackmagic_raw_frame_get_timecode(IBlackmagicRawFrame* frame, Buffer** out);
+HRESULT blackmagic_raw_frame_get_metadata_iterator(IBlackmagicRawFrame* frame, IBlackmagicRawMetadataIterator** iterator);
+HRESULT blackmagic_raw_frame_get_metadata(IBlackmagicRawFrame* frame, const char* key, Variant* value);
+HRESULT blackmagic_raw_frame_set_metadata(IBlackmagicRawFrame* frame, const char* key, Variant* value);
+HRESULT blackmagic_raw_frame_clone_frame_processing_attributes(IBlackmagicRawFrame* frame, IBlackmagicRawFrameProcessingAttributes** out);
 HRESULT blackmagic_raw_frame_set_resource_format(IBlackmagicRawFrame* frame, BlackmagicRawResourceFormat format);
+HRESULT blackmagic_raw_frame_get_resource_format(IBlackmagicRawFrame* frame, BlackmagicRawResourceFormat* out);
+HRESULT blackmagic_raw_frame_set_resolution_scale(IBlackmagicRawFrame* frame, BlackmagicRawResolutionScale scale);
+HRESULT blackmagic_raw_frame_get_resolution_scale(IBlackmagicRawFrame* frame, BlackmagicRawResolutionScale* out);
 HRESULT blackmagic_raw_frame_create_job_decode_and_process_frame(IBlackmagicRawFrame* frame, IBlackmagicRawClipProcessingAttributes* clipProcessingAttributes, IBlackmagicRawFrameProcessingAttributes* frameProcessingAttributes, IBlackmagicRawJob** job);
 
+HRESULT blackmagic_raw_clip_processing_attributes_get_clip_attribute(IBlackmagicRawClipProcessingAttributes* attributes, BlackmagicRawClipProcessingAttribute attribute, Variant* value);
+HRESULT blackmagic_raw_clip_processing_attributes_set_clip_attribute(IBlackmagicRawClipProcessingAttributes* attributes, BlackmagicRawClipProcessingAttribute attribute, Variant* value);
+HRESULT blackmagic_raw_clip_processing_attributes_get_post_3d_lut(IBlackmagicRawClipProcessingAttributes* attributes, IBlackmagicRawPost3DLUT** lut);
+
+HRESULT blackmagic_raw_post_3d_lut_get_name(IBlackmagicRawPost3DLUT* lut, Buffer** out);
+HRESULT blackmagic_raw_post_3d_lut_get_title(IBlackmagicRawPost3DLUT* lut, Buffer** out);
+HRESULT blackmagic_raw_post_3d_lut_get_size(IBlackmagicRawPost3DLUT* lut, uint32_t* out);
+HRESULT blackmagic_raw_post_3d_lut_get_resource_cpu(IBlackmagicRawPost3DLUT* lut, void** out);
+HRESULT blackmagic_raw_post_3d_lut_get_resource_size_bytes(IBlackmagicRawPost3DLUT* lut, uint32_t* out);
+
+HRESULT blackmagic_raw_frame_processing_attributes_get_frame_attribute(IBlackmagicRawFrameProcessingAttributes* attributes, BlackmagicRawFrameProcessingAttribute attribute, Variant* value);
+HRESULT blackmagic_raw_frame_processing_attributes_set_frame_attribute(IBlackmagicRawFrameProcessingAttributes* attributes, BlackmagicRawFrameProcessingAttribute attribute, Variant* value);
+
+HRESULT blackmagic_raw_constants_get_clip_processing_attribute_range(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawClipProcessingAttribute attribute, Variant* valueMin, Variant* valueMax);
+HRESULT blackmagic_raw_constants_get_clip_processing_attribute_list(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawClipProcessingAttribute attribute, Variant* array, uint32_t* arrayElementCount);
+HRESULT blackmagic_raw_constants_get_frame_processing_attribute_range(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawFrameProcessingAttribute attribute, Variant* valueMin, Variant* valueMax);
+HRESULT blackmagic_raw_constants_get_frame_processing_attribute_list(IBlackmagicRawConstants* constants, const char* cameraType, BlackmagicRawFrameProcessingAttribute attribute, Variant* array, uint32_t* arrayElementCount);
+HRESULT blackmagic_raw_constants_get_iso_list_for_analog_gain(IBlackmagicRawConstants* constants, const char* cameraType, float analogGain, uint16_t* array, uint32_t* arrayElementCount);
+
+HRESULT blackmagic_raw_tone_curve_get_tone_curve(IBlackmagicRawToneCurve* toneCurve, const char* cameraType, const char* gamma, uint16_t gen, float* contrast, float* saturation, float* midpoint, float* highlights, float* shadows, float* blackLevel, float* whiteLevel, uint16_t* videoBlackLevel);
+HRESULT blackmagic_raw_tone_curve_evaluate_tone_curve(IBlackmagicRawToneCurve* toneCurve, const char* cameraType, uint16_t gen, float contrast, float saturation, float midpoint, float highlights, float shadows, float blackLevel, float whiteLevel, uint16_t videoBlackLevel, float* array, uint32_t arrayElementCount);
+
 HRESULT blackmagic_raw_processed_image_get_width(IBlackmagicRawProcessedImage* img, uint32_t* out);
 HRESULT blackmagic_raw_processed_image_get_height(IBlackmagicRawProcessedImage* img, uint32_t* out);
 HRESULT blackmagic_raw_processed_image_get_resource_size_bytes(IBlackmagicRawProcessedImage* img, uint32_t* out);
 HRESULT blackmagic_raw_processed_image_get_resource(IBlackmagicRawProcessedImage* img, void** bytes);
+HRESULT blackmagic_raw_processed_image_get_resource_type(IBlackmagicRawProcessedImage* img, BlackmagicRawResourceType* out);
+HRESULT blackmagic_raw_processed_image_get_resource_format(IBlackmagicRawProcessedImage* img, BlackmagicRawResourceFormat* out);
 
 IBlackmagicRawCallback* create_blackmagic_raw_callback(void* implementation);
+IBlackmagicRawResourceManager* create_blackmagic_raw_resource_manager(void* implementation);
 
 const void* buffer_data(Buffer* str);
 void buffer_release(Buffer* str);
 
 void blackmagic_raw_variant_get_string(Variant* v, Buffer** out);
+void blackmagic_raw_variant_set_string(Variant* v, const char* s);
+void blackmagic_raw_variant_release_string(Variant* v);
 
 }
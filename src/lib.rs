@@ -1,44 +1,223 @@
 #![allow(non_snake_case, non_upper_case_globals, non_camel_case_types)]
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
-#[macro_use] extern crate simple_error;
-
+extern crate futures_core;
+
+use std::alloc::{alloc, dealloc, Layout};
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
 use std::ffi::{c_void, CStr, CString};
 use std::fmt;
 use std::os::raw::{c_char, c_float};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+
+mod decode_range;
+mod frame_rate;
+mod future;
+mod lut;
+mod timecode;
+pub use decode_range::DecodeRange;
+pub use frame_rate::FrameRate;
+pub use future::{JobFuture, TrimFuture, TrimProgress};
+pub use lut::{CubeLut, CubeParseError, Post3DLut};
+pub use timecode::Timecode;
+
+/// The failure codes returned by SDK calls. The common COM codes have their own variants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    Unexpected,
+    NotImpl,
+    OutOfMemory,
+    InvalidArg,
+    NoInterface,
+    Pointer,
+    Handle,
+    Abort,
+    Fail,
+    AccessDenied,
+    Other(HRESULT),
+}
+
+impl ErrorCode {
+    pub fn from_hresult(result: HRESULT) -> ErrorCode {
+        match result as u32 {
+            0x8000FFFF => ErrorCode::Unexpected,
+            0x80000001 => ErrorCode::NotImpl,
+            0x80000002 => ErrorCode::OutOfMemory,
+            0x80000003 => ErrorCode::InvalidArg,
+            0x80000004 => ErrorCode::NoInterface,
+            0x80000005 => ErrorCode::Pointer,
+            0x80000006 => ErrorCode::Handle,
+            0x80000007 => ErrorCode::Abort,
+            0x80000008 => ErrorCode::Fail,
+            0x80000009 => ErrorCode::AccessDenied,
+            _ => ErrorCode::Other(result),
+        }
+    }
+
+    pub fn hresult(self) -> HRESULT {
+        let code: u32 = match self {
+            ErrorCode::Unexpected => 0x8000FFFF,
+            ErrorCode::NotImpl => 0x80000001,
+            ErrorCode::OutOfMemory => 0x80000002,
+            ErrorCode::InvalidArg => 0x80000003,
+            ErrorCode::NoInterface => 0x80000004,
+            ErrorCode::Pointer => 0x80000005,
+            ErrorCode::Handle => 0x80000006,
+            ErrorCode::Abort => 0x80000007,
+            ErrorCode::Fail => 0x80000008,
+            ErrorCode::AccessDenied => 0x80000009,
+            ErrorCode::Other(result) => return result,
+        };
+        code as HRESULT
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorCode::Unexpected => write!(f, "E_UNEXPECTED"),
+            ErrorCode::NotImpl => write!(f, "E_NOTIMPL"),
+            ErrorCode::OutOfMemory => write!(f, "E_OUTOFMEMORY"),
+            ErrorCode::InvalidArg => write!(f, "E_INVALIDARG"),
+            ErrorCode::NoInterface => write!(f, "E_NOINTERFACE"),
+            ErrorCode::Pointer => write!(f, "E_POINTER"),
+            ErrorCode::Handle => write!(f, "E_HANDLE"),
+            ErrorCode::Abort => write!(f, "E_ABORT"),
+            ErrorCode::Fail => write!(f, "E_FAIL"),
+            ErrorCode::AccessDenied => write!(f, "E_ACCESSDENIED"),
+            ErrorCode::Other(result) => write!(f, "HRESULT {:#010x}", result as u32),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Error {
+    /// An SDK call failed. `operation` names the call, and `path` is the file it was working on
+    /// if there is one.
+    Sdk {
+        code: ErrorCode,
+        operation: &'static str,
+        path: Option<String>,
+    },
+    /// A path or string argument couldn't be passed to the SDK because it contains a nul byte.
+    InteriorNul {
+        operation: &'static str,
+        value: String,
+    },
+    /// The SDK library couldn't be loaded.
+    Load(LoadError),
+    /// A value was rejected before being passed to the SDK, for example because it's outside the
+    /// range the camera supports.
+    InvalidValue {
+        operation: &'static str,
+        message: String,
+    },
+}
 
-#[derive(Debug)]
-pub struct Error {
-    pub result: HRESULT,
+impl Error {
+    pub fn new(code: ErrorCode, operation: &'static str) -> Error {
+        Error::Sdk{
+            code: code,
+            operation: operation,
+            path: None,
+        }
+    }
+
+    /// Returns the SDK error code, if this error came from an SDK call.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match *self {
+            Error::Sdk{code, ..} => Some(code),
+            _ => None,
+        }
+    }
+
+    fn with_path<P: AsRef<Path>>(self, p: P) -> Error {
+        match self {
+            Error::Sdk{code, operation, ..} => Error::Sdk{
+                code: code,
+                operation: operation,
+                path: Some(p.as_ref().to_string_lossy().into_owned()),
+            },
+            other => other,
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "blackmagic raw error: {}", self.result)
+        match *self {
+            Error::Sdk{code, operation, ref path} => {
+                write!(f, "blackmagic raw error: {} from {}", code, operation)?;
+                if let Some(ref path) = *path {
+                    write!(f, " ({})", path)?;
+                }
+                Ok(())
+            },
+            Error::InteriorNul{operation, ref value} => write!(f, "blackmagic raw error: {:?} passed to {} contains a nul byte", value, operation),
+            Error::Load(ref err) => err.fmt(f),
+            Error::InvalidValue{operation, ref message} => write!(f, "blackmagic raw error: invalid value passed to {}: {}", operation, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Load(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LoadError> for Error {
+    fn from(err: LoadError) -> Error {
+        Error::Load(err)
     }
 }
 
-impl std::error::Error for Error {}
+fn c_string(s: &str, operation: &'static str) -> Result<CString, Error> {
+    CString::new(s).map_err(|_| Error::InteriorNul{
+        operation: operation,
+        value: s.to_string(),
+    })
+}
+
+fn c_path(path: &Path, operation: &'static str) -> Result<CString, Error> {
+    match path.to_str() {
+        Some(s) => c_string(s, operation),
+        None => Err(Error::InvalidValue{
+            operation: operation,
+            message: format!("{:?} isn't valid UTF-8", path),
+        }),
+    }
+}
 
-fn void_result(result: HRESULT) -> Result<(), Error> {
+fn void_result(result: HRESULT, operation: &'static str) -> Result<(), Error> {
     match result {
         0 => Ok(()),
-        result => Err(Error{
-            result: result,
-        }),
+        result => Err(Error::new(ErrorCode::from_hresult(result), operation)),
     }
 }
 
-fn void_option_result(result: HRESULT) -> Result<Option<()>, Error> {
+fn void_option_result(result: HRESULT, operation: &'static str) -> Result<Option<()>, Error> {
     match result {
         0 => Ok(Some(())),
         1 => Ok(None),
-        result => Err(Error{
-            result: result,
-        }),
+        result => Err(Error::new(ErrorCode::from_hresult(result), operation)),
     }
 }
 
+unsafe fn query_interface<T>(obj: *mut IUnknown, iid: REFIID) -> Result<Option<*mut T>, Error> {
+    let mut iface: *mut T = std::ptr::null_mut();
+    Ok(void_option_result(blackmagic_raw_unknown_query_interface(obj, iid, std::mem::transmute::<&mut *mut T, &mut *mut c_void>(&mut iface)), "IUnknown::QueryInterface")?.map(|_| iface))
+}
+
 impl REFIID {
     fn new(b: [u8; 16]) -> REFIID {
         REFIID{
@@ -77,101 +256,284 @@ impl Drop for Factory {
     }
 }
 
-impl Factory {
-    pub fn new_from_path(path: &str) -> Result<Factory, Box<dyn std::error::Error>> {
-        unsafe {
-            let path = CString::new(path)?;
-            let iterator = create_blackmagic_raw_factory_instance_from_path(path.as_ptr());
-            if iterator.is_null() {
-                bail!("unable to create blackmagic raw factory. the latest drivers may need to be installed");
+/// The environment variable checked first by `Factory::discover`. It should name the directory
+/// containing the SDK library.
+pub const SDK_PATH_ENV_VAR: &str = "BLACKMAGIC_RAW_SDK_PATH";
+
+/// The directories checked by `Factory::discover` after the environment variable.
+#[cfg(target_os = "macos")]
+pub const DEFAULT_SDK_PATHS: &[&str] = &[
+    "/Applications/Blackmagic RAW/Blackmagic RAW SDK/Mac/Libraries",
+    "/Applications/Blackmagic RAW/Blackmagic RAW Player.app/Contents/Frameworks",
+    "/Applications/DaVinci Resolve/DaVinci Resolve.app/Contents/Libraries",
+    "/Library/Frameworks",
+];
+
+/// The directories checked by `Factory::discover` after the environment variable.
+#[cfg(not(target_os = "macos"))]
+pub const DEFAULT_SDK_PATHS: &[&str] = &[
+    "/usr/lib64/blackmagic/BlackmagicRAWSDK/Linux/Libraries",
+    "/usr/lib/blackmagic/BlackmagicRAWSDK/Linux/Libraries",
+    "/opt/BlackmagicRAWPlayer/BlackmagicRawAPI",
+    "/opt/resolve/libs",
+];
+
+#[cfg(target_os = "macos")]
+const SDK_LIBRARY_NAME: &str = "BlackmagicRawAPI.framework";
+
+#[cfg(not(target_os = "macos"))]
+const SDK_LIBRARY_NAME: &str = "libBlackmagicRawAPI.so";
+
+#[derive(Clone, Debug)]
+pub struct LoadAttempt {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Returned when the SDK library can't be loaded. It lists every location that was tried.
+#[derive(Clone, Debug)]
+pub struct LoadError {
+    pub attempts: Vec<LoadAttempt>,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unable to create blackmagic raw factory. the latest drivers may need to be installed")?;
+        if !self.attempts.is_empty() {
+            write!(f, ". tried:")?;
+            for attempt in &self.attempts {
+                write!(f, "\n  {}: {}", attempt.path, attempt.reason)?;
             }
-            return Ok(Factory{
-                implementation: iterator,
-            });
         }
+        Ok(())
     }
+}
 
-    pub fn create_codec(&self) -> Result<Codec, Error> {
-        let mut codec: *mut IBlackmagicRaw = std::ptr::null_mut();
-        unsafe {
-            void_result(blackmagic_raw_factory_create_codec(self.implementation, &mut codec))?;
+impl std::error::Error for LoadError {}
+
+impl LoadAttempt {
+    fn for_path(path: &str) -> LoadAttempt {
+        let dir = Path::new(path);
+        let reason = if !dir.is_dir() {
+            "directory does not exist".to_string()
+        } else if !dir.join(SDK_LIBRARY_NAME).exists() {
+            format!("{} not found in directory", SDK_LIBRARY_NAME)
+        } else {
+            format!("{} could not be loaded", SDK_LIBRARY_NAME)
+        };
+        LoadAttempt{
+            path: path.to_string(),
+            reason: reason,
         }
-        return Ok(Codec{
-            implementation: codec,
-        });
     }
 }
 
-pub struct Codec {
-    implementation: *mut IBlackmagicRaw,
-}
-
-unsafe impl Send for Codec {}
+impl Factory {
+    unsafe fn from_instance(factory: *mut IBlackmagicRawFactory, attempt: impl FnOnce() -> LoadAttempt) -> Result<Factory, LoadError> {
+        if factory.is_null() {
+            return Err(LoadError{
+                attempts: vec![attempt()],
+            });
+        }
+        Ok(Factory{
+            implementation: factory,
+        })
+    }
 
-impl Drop for Codec {
-    fn drop(&mut self) {
+    /// Loads the SDK using its own search order: a "BlackmagicRawAPI" directory next to the
+    /// executable, the executable's directory, then the system library search path.
+    pub fn new() -> Result<Factory, Error> {
         unsafe {
-            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+            Ok(Factory::from_instance(create_blackmagic_raw_factory_instance(), || LoadAttempt{
+                path: "<default search path>".to_string(),
+                reason: format!("{} could not be loaded", SDK_LIBRARY_NAME),
+            })?)
         }
     }
-}
 
-impl Codec {
-    pub fn open_clip(&mut self, path: &str) -> Result<Clip, Box<dyn std::error::Error>> {
-        let path = CString::new(path)?;
-        let mut clip: *mut IBlackmagicRawClip = std::ptr::null_mut();
+    pub fn new_from_path(path: &str) -> Result<Factory, Error> {
         unsafe {
-            void_result(blackmagic_raw_open_clip(self.implementation, path.as_ptr(), &mut clip))?;
+            let c_path = c_string(path, "CreateBlackmagicRawFactoryInstanceFromPath")?;
+            Ok(Factory::from_instance(create_blackmagic_raw_factory_instance_from_path(c_path.as_ptr()), || LoadAttempt::for_path(path))?)
         }
-        return Ok(Clip{
-            implementation: clip,
-        });
     }
 
-    pub fn with_callback<'a, T, F, V>(&mut self, callback: T, f: F) -> Result<V, Box<dyn std::error::Error>>
-        where T: Callback + Send + 'a,
-              F: FnOnce(&mut Codec) -> V,
-    {
-        let mut callback: Box<dyn Callback + Send + 'a> = Box::new(callback);
+    /// Loads the SDK from a directory relative to the directory containing the executable.
+    pub fn new_from_exe_relative_path(path: &str) -> Result<Factory, Error> {
         unsafe {
-            self.set_callback(Some(std::mem::transmute::<&mut Box::<dyn Callback + Send + 'a>, &mut Box::<dyn Callback + Send>>(&mut callback)))?;
+            let c_path = c_string(path, "CreateBlackmagicRawFactoryInstanceFromExeRelativePath")?;
+            Ok(Factory::from_instance(create_blackmagic_raw_factory_instance_from_exe_relative_path(c_path.as_ptr()), || LoadAttempt{
+                path: format!("<executable directory>/{}", path),
+                reason: format!("{} could not be loaded", SDK_LIBRARY_NAME),
+            })?)
+        }
+    }
+
+    /// Loads the SDK from the directory named by `SDK_PATH_ENV_VAR` if it's set, then from each of
+    /// `DEFAULT_SDK_PATHS`, then from the SDK's default search path. If nothing loads, the error
+    /// lists every location that was tried and why it failed.
+    ///
+    /// Note that the SDK only ever loads one library per process. Once any constructor has
+    /// succeeded, later ones reuse that library regardless of the path given.
+    pub fn discover() -> Result<Factory, Error> {
+        let mut candidates = Vec::new();
+        if let Ok(path) = env::var(SDK_PATH_ENV_VAR) {
+            if !path.is_empty() {
+                candidates.push(path);
+            }
+        }
+        candidates.extend(DEFAULT_SDK_PATHS.iter().map(|path| path.to_string()));
+
+        let mut attempts = Vec::new();
+        for path in candidates {
+            let c_path = match CString::new(path.as_str()) {
+                Ok(p) => p,
+                Err(_) => {
+                    attempts.push(LoadAttempt{
+                        path: path,
+                        reason: "path contains a nul byte".to_string(),
+                    });
+                    continue;
+                },
+            };
+            match unsafe { Factory::from_instance(create_blackmagic_raw_factory_instance_from_path(c_path.as_ptr()), || LoadAttempt::for_path(&path)) } {
+                Ok(factory) => return Ok(factory),
+                Err(mut err) => attempts.append(&mut err.attempts),
+            }
+        }
+
+        let factory = unsafe {
+            Factory::from_instance(create_blackmagic_raw_factory_instance(), || LoadAttempt{
+                path: "<default search path>".to_string(),
+                reason: format!("{} could not be loaded", SDK_LIBRARY_NAME),
+            })
+        };
+        match factory {
+            Ok(factory) => Ok(factory),
+            Err(mut err) => {
+                attempts.append(&mut err.attempts);
+                Err(Error::Load(LoadError{
+                    attempts: attempts,
+                }))
+            },
         }
-        let ret = f(self);
+    }
+
+    pub fn create_codec(&self) -> Result<Codec, Error> {
+        let mut codec: *mut IBlackmagicRaw = std::ptr::null_mut();
         unsafe {
-            self.set_callback(None)?;
+            void_result(blackmagic_raw_factory_create_codec(self.implementation, &mut codec), "IBlackmagicRawFactory::CreateCodec")?;
         }
-        Ok(ret)
+        let mut codec = Codec{
+            implementation: codec,
+            callback_panic: Arc::new(Mutex::new(None)),
+        };
+        // A callback is always registered so that per-job handlers are invoked even if the caller
+        // never sets one.
+        codec.register_callback(Arc::new(Mutex::new(NoCallback)))?;
+        return Ok(codec);
     }
 
-    /// The caller must ensure that the given callback lives until the callback is unset. Use with_callback for a safer alternative.
-    pub unsafe fn set_callback(&mut self, callback: Option<&mut Box<dyn Callback + Send>>) -> Result<(), Error> {
-        match callback {
-            Some(callback) => {
-                let callback = create_blackmagic_raw_callback(callback as *mut Box<dyn Callback + Send> as *mut c_void);
-                let result = void_result(blackmagic_raw_set_callback(self.implementation, callback));
-                blackmagic_raw_unknown_release(callback as *mut IUnknown);
-                result
-            },
-            None => {
-                void_result(blackmagic_raw_set_callback(self.implementation, std::ptr::null_mut()))
-            },
+    /// Returns a builder that creates a codec and configures it before it can open any clips.
+    pub fn codec_builder(&self) -> CodecBuilder<'_> {
+        CodecBuilder{
+            factory: self,
+            cpu_threads: None,
+            pipeline: None,
+            device: None,
+            write_metadata_per_frame: None,
+            instruction_set: None,
+            resource_manager: None,
         }
     }
 
-    pub fn flush_jobs(&mut self) -> Result<(), Error> {
+    /// Returns an iterator over the pipelines that are available on this machine.
+    pub fn create_pipeline_iterator(&self, interop: Interop) -> Result<PipelineIterator, Error> {
+        let mut iface: *mut IBlackmagicRawPipelineIterator = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_factory_create_pipeline_iterator(self.implementation, interop.0, &mut iface), "IBlackmagicRawFactory::CreatePipelineIterator")?;
+        }
+        return Ok(PipelineIterator{
+            implementation: iface,
+            started: false,
+        });
+    }
+
+    /// Returns an iterator over the devices that can run the given pipeline.
+    pub fn create_pipeline_device_iterator(&self, pipeline: Pipeline, interop: Interop) -> Result<PipelineDeviceIterator, Error> {
+        let mut iface: *mut IBlackmagicRawPipelineDeviceIterator = std::ptr::null_mut();
         unsafe {
-            void_result(blackmagic_raw_flush_jobs(self.implementation))
+            void_result(blackmagic_raw_factory_create_pipeline_device_iterator(self.implementation, pipeline.0, interop.0, &mut iface), "IBlackmagicRawFactory::CreatePipelineDeviceIterator")?;
         }
+        return Ok(PipelineDeviceIterator{
+            implementation: iface,
+            started: false,
+        });
     }
 }
 
-pub struct Clip {
-    implementation: *mut IBlackmagicRawClip,
+unsafe fn buffer_to_string(buf: *mut Buffer) -> String {
+    if buf.is_null() {
+        return String::new();
+    }
+    let s = CStr::from_ptr(buffer_data(buf) as *const c_char).to_str().unwrap_or("").to_string();
+    buffer_release(buf);
+    s
 }
 
-unsafe impl Send for Clip {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pipeline(pub u32);
 
-impl Drop for Clip {
+impl Pipeline {
+    pub const PIPELINE_CPU: Pipeline = Pipeline(_BlackmagicRawPipeline_blackmagicRawPipelineCPU);
+    pub const PIPELINE_CUDA: Pipeline = Pipeline(_BlackmagicRawPipeline_blackmagicRawPipelineCUDA);
+    pub const PIPELINE_METAL: Pipeline = Pipeline(_BlackmagicRawPipeline_blackmagicRawPipelineMetal);
+    pub const PIPELINE_OPENCL: Pipeline = Pipeline(_BlackmagicRawPipeline_blackmagicRawPipelineOpenCL);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interop(pub u32);
+
+impl Interop {
+    pub const INTEROP_NONE: Interop = Interop(_BlackmagicRawInterop_blackmagicRawInteropNone);
+    pub const INTEROP_OPENGL: Interop = Interop(_BlackmagicRawInterop_blackmagicRawInteropOpenGL);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstructionSet(pub u32);
+
+impl InstructionSet {
+    pub const INSTRUCTION_SET_SSE41: InstructionSet = InstructionSet(_BlackmagicRawInstructionSet_blackmagicRawInstructionSetSSE41);
+    pub const INSTRUCTION_SET_AVX: InstructionSet = InstructionSet(_BlackmagicRawInstructionSet_blackmagicRawInstructionSetAVX);
+    pub const INSTRUCTION_SET_AVX2: InstructionSet = InstructionSet(_BlackmagicRawInstructionSet_blackmagicRawInstructionSetAVX2);
+}
+
+impl fmt::Display for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InstructionSet::INSTRUCTION_SET_SSE41 => write!(f, "SSE4.1"),
+            InstructionSet::INSTRUCTION_SET_AVX => write!(f, "AVX"),
+            InstructionSet::INSTRUCTION_SET_AVX2 => write!(f, "AVX2"),
+            InstructionSet(other) => write!(f, "unknown instruction set {:#x}", other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PipelineInfo {
+    pub name: String,
+    pub pipeline: Pipeline,
+    pub interop: Interop,
+}
+
+pub struct PipelineIterator {
+    implementation: *mut IBlackmagicRawPipelineIterator,
+    started: bool,
+}
+
+unsafe impl Send for PipelineIterator {}
+
+impl Drop for PipelineIterator {
     fn drop(&mut self) {
         unsafe {
             blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
@@ -179,139 +541,162 @@ impl Drop for Clip {
     }
 }
 
-impl Clip {
-    unsafe fn new_ref(clip: *mut IBlackmagicRawClip) -> Clip {
-        blackmagic_raw_unknown_add_ref(clip as *mut IUnknown);
-        Clip{
-            implementation: clip,
-        }
-    }
+impl std::iter::Iterator for PipelineIterator {
+    type Item = PipelineInfo;
 
-    pub fn get_width(&mut self) -> Result<u32, Error> {
-        let mut width = 0;
+    fn next(&mut self) -> Option<PipelineInfo> {
         unsafe {
-            void_result(blackmagic_raw_clip_get_width(self.implementation, &mut width))?;
+            // The iterator starts out positioned on the first entry.
+            if self.started {
+                match void_option_result(blackmagic_raw_pipeline_iterator_next(self.implementation), "IBlackmagicRawPipelineIterator::Next") {
+                    Ok(Some(_)) => {},
+                    _ => return None,
+                };
+            }
+            self.started = true;
+
+            let mut buf: *mut Buffer = std::ptr::null_mut();
+            void_result(blackmagic_raw_pipeline_iterator_get_name(self.implementation, &mut buf), "IBlackmagicRawPipelineIterator::GetName").ok()?;
+            let name = buffer_to_string(buf);
+
+            let mut pipeline = 0;
+            void_result(blackmagic_raw_pipeline_iterator_get_pipeline(self.implementation, &mut pipeline), "IBlackmagicRawPipelineIterator::GetPipeline").ok()?;
+
+            let mut interop = 0;
+            void_result(blackmagic_raw_pipeline_iterator_get_interop(self.implementation, &mut interop), "IBlackmagicRawPipelineIterator::GetInterop").ok()?;
+
+            Some(PipelineInfo{
+                name: name,
+                pipeline: Pipeline(pipeline),
+                interop: Interop(interop),
+            })
         }
-        return Ok(width)
     }
+}
 
-    pub fn get_height(&mut self) -> Result<u32, Error> {
-        let mut height = 0;
+pub struct PipelineDeviceIterator {
+    implementation: *mut IBlackmagicRawPipelineDeviceIterator,
+    started: bool,
+}
+
+unsafe impl Send for PipelineDeviceIterator {}
+
+impl Drop for PipelineDeviceIterator {
+    fn drop(&mut self) {
         unsafe {
-            void_result(blackmagic_raw_clip_get_height(self.implementation, &mut height))?;
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
         }
-        return Ok(height)
     }
+}
 
-    pub fn get_frame_rate(&mut self) -> Result<f32, Error> {
-        let mut frame_rate = 0.0;
+impl std::iter::Iterator for PipelineDeviceIterator {
+    type Item = PipelineDevice;
+
+    fn next(&mut self) -> Option<PipelineDevice> {
         unsafe {
-            void_result(blackmagic_raw_clip_get_frame_rate(self.implementation, &mut frame_rate))?;
+            // The iterator starts out positioned on the first entry.
+            if self.started {
+                match void_option_result(blackmagic_raw_pipeline_device_iterator_next(self.implementation), "IBlackmagicRawPipelineDeviceIterator::Next") {
+                    Ok(Some(_)) => {},
+                    _ => return None,
+                };
+            }
+            self.started = true;
+
+            let mut device: *mut IBlackmagicRawPipelineDevice = std::ptr::null_mut();
+            void_result(blackmagic_raw_pipeline_device_iterator_create_device(self.implementation, &mut device), "IBlackmagicRawPipelineDeviceIterator::CreateDevice").ok()?;
+            Some(PipelineDevice{
+                implementation: device,
+            })
         }
-        return Ok(frame_rate)
     }
+}
 
-    pub fn get_frame_count(&mut self) -> Result<u64, Error> {
-        let mut frame_count = 0;
+pub struct PipelineDevice {
+    implementation: *mut IBlackmagicRawPipelineDevice,
+}
+
+unsafe impl Send for PipelineDevice {}
+
+impl Drop for PipelineDevice {
+    fn drop(&mut self) {
         unsafe {
-            void_result(blackmagic_raw_clip_get_frame_count(self.implementation, &mut frame_count))?;
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
         }
-        return Ok(frame_count)
     }
+}
 
-    pub fn get_metadata_iterator(&mut self) -> Result<MetadataIterator, Error> {
-        let mut iface: *mut IBlackmagicRawMetadataIterator = std::ptr::null_mut();
+impl PipelineDevice {
+    /// Selects the fastest instruction set supported by this machine.
+    pub fn set_best_instruction_set(&mut self) -> Result<(), Error> {
         unsafe {
-            void_result(blackmagic_raw_clip_get_metadata_iterator(self.implementation, &mut iface))?;
+            void_result(blackmagic_raw_pipeline_device_set_best_instruction_set(self.implementation), "IBlackmagicRawPipelineDevice::SetBestInstructionSet")
         }
-        return Ok(MetadataIterator{
-            implementation: iface,
-        })
     }
 
-    unsafe fn query_interface<T>(&self, iid: REFIID) -> Result<Option<*mut T>, Error> {
-            let mut iface: *mut T = std::ptr::null_mut();
-            Ok(void_option_result(blackmagic_raw_unknown_query_interface(self.implementation as *mut IUnknown, iid, std::mem::transmute::<&mut *mut T, &mut *mut c_void>(&mut iface)))?.map(|_| iface))
-    }
-
-    pub fn get_audio(&mut self) -> Result<Option<ClipAudio>, Error> {
+    pub fn set_instruction_set(&mut self, instruction_set: InstructionSet) -> Result<(), Error> {
         unsafe {
-            Ok(self.query_interface::<IBlackmagicRawClipAudio>(REFIID::new([0x76,0xD4,0xAC,0xED,0xE0,0xD6,0x45,0xBB,0xB5,0x47,0x56,0xB7,0x43,0x5B,0x2A,0x1D]))?.map(|audio| ClipAudio{
-                implementation: audio,
-            }))
+            void_result(blackmagic_raw_pipeline_device_set_instruction_set(self.implementation, instruction_set.0), "IBlackmagicRawPipelineDevice::SetInstructionSet")
         }
     }
 
-    pub fn create_job_read_frame(&mut self, frame: u64) -> Result<Job, Error> {
-        let mut job: *mut IBlackmagicRawJob = std::ptr::null_mut();
+    pub fn get_instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        let mut ret = 0;
         unsafe {
-            void_result(blackmagic_raw_clip_create_job_read_frame(self.implementation, frame, &mut job))?;
+            void_result(blackmagic_raw_pipeline_device_get_instruction_set(self.implementation, &mut ret), "IBlackmagicRawPipelineDevice::GetInstructionSet")?;
         }
-        return Ok(Job{
-            implementation: job,
-        });
+        return Ok(InstructionSet(ret))
     }
 
-    // TODO: support attributes arguments
-    pub fn create_job_trim(&mut self, file_name: String, frame_index: u64, frame_count: u64) -> Result<Job, Box<dyn std::error::Error>> {
-        let mut job: *mut IBlackmagicRawJob = std::ptr::null_mut();
-        let file_name = CString::new(file_name)?;
+    pub fn get_index(&mut self) -> Result<u32, Error> {
+        let mut ret = 0;
         unsafe {
-            void_result(blackmagic_raw_clip_create_job_trim(self.implementation, file_name.as_ptr(), frame_index, frame_count, std::ptr::null_mut(), std::ptr::null_mut(), &mut job))?;
+            void_result(blackmagic_raw_pipeline_device_get_index(self.implementation, &mut ret), "IBlackmagicRawPipelineDevice::GetIndex")?;
         }
-        return Ok(Job{
-            implementation: job,
-        });
+        return Ok(ret)
     }
-}
 
-pub struct ClipAudio {
-    implementation: *mut IBlackmagicRawClipAudio,
-}
-
-unsafe impl Send for ClipAudio {}
-
-impl Drop for ClipAudio {
-    fn drop(&mut self) {
+    pub fn get_name(&mut self) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
         unsafe {
-            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+            void_result(blackmagic_raw_pipeline_device_get_name(self.implementation, &mut buf), "IBlackmagicRawPipelineDevice::GetName")?;
+            Ok(buffer_to_string(buf))
         }
     }
-}
 
-impl ClipAudio {
-    pub fn get_channel_count(&mut self) -> Result<u32, Error> {
+    pub fn get_interop(&mut self) -> Result<Interop, Error> {
         let mut ret = 0;
         unsafe {
-            void_result(blackmagic_raw_clip_audio_get_channel_count(self.implementation, &mut ret))?;
+            void_result(blackmagic_raw_pipeline_device_get_interop(self.implementation, &mut ret), "IBlackmagicRawPipelineDevice::GetInterop")?;
         }
-        return Ok(ret)
+        return Ok(Interop(ret))
     }
 
-    pub fn get_sample_rate(&mut self) -> Result<u32, Error> {
+    pub fn get_pipeline(&mut self) -> Result<Pipeline, Error> {
         let mut ret = 0;
         unsafe {
-            void_result(blackmagic_raw_clip_audio_get_sample_rate(self.implementation, &mut ret))?;
+            void_result(blackmagic_raw_pipeline_device_get_pipeline(self.implementation, &mut ret), "IBlackmagicRawPipelineDevice::GetPipeline")?;
         }
-        return Ok(ret)
+        return Ok(Pipeline(ret))
     }
 
-    pub fn get_sample_count(&mut self) -> Result<u64, Error> {
-        let mut ret = 0;
+    pub fn get_pipeline_name(&mut self) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
         unsafe {
-            void_result(blackmagic_raw_clip_audio_get_sample_count(self.implementation, &mut ret))?;
+            void_result(blackmagic_raw_pipeline_device_get_pipeline_name(self.implementation, &mut buf), "IBlackmagicRawPipelineDevice::GetPipelineName")?;
+            Ok(buffer_to_string(buf))
         }
-        return Ok(ret)
     }
 }
 
-pub struct Job {
-    implementation: *mut IBlackmagicRawJob,
+pub struct Codec {
+    implementation: *mut IBlackmagicRaw,
+    callback_panic: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
 }
 
-unsafe impl Send for Job {}
+unsafe impl Send for Codec {}
 
-impl Drop for Job {
+impl Drop for Codec {
     fn drop(&mut self) {
         unsafe {
             blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
@@ -319,43 +704,228 @@ impl Drop for Job {
     }
 }
 
-impl Job {
-    unsafe fn new_ref(job: *mut IBlackmagicRawJob) -> Job {
-        blackmagic_raw_unknown_add_ref(job as *mut IUnknown);
-        Job{
-            implementation: job,
+impl Codec {
+    pub fn open_clip(&mut self, path: &str) -> Result<Clip, Error> {
+        let c_path = c_string(path, "IBlackmagicRaw::OpenClip")?;
+        let mut clip: *mut IBlackmagicRawClip = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_open_clip(self.implementation, c_path.as_ptr(), &mut clip), "IBlackmagicRaw::OpenClip").map_err(|e| e.with_path(path))?;
         }
+        return Ok(Clip{
+            implementation: clip,
+        });
     }
 
-    pub fn submit(&mut self) -> Result<(), Error> {
+    /// Installs the callback for the duration of `f`, flushing any outstanding jobs before it's
+    /// removed again. If the callback panics, the panic is resumed here.
+    pub fn with_callback<T, F, V>(&mut self, callback: T, f: F) -> Result<V, Error>
+        where T: Callback + Send + 'static,
+              F: FnOnce(&mut Codec) -> V,
+    {
+        self.set_callback(Arc::new(Mutex::new(callback)))?;
+        let ret = match panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(ret) => ret,
+            Err(payload) => {
+                // Jobs submitted by `f` may still be running, so they need to finish before the
+                // callback goes away. Their panics, if any, are superseded by this one.
+                unsafe {
+                    blackmagic_raw_flush_jobs(self.implementation);
+                }
+                let _ = self.register_callback(Arc::new(Mutex::new(NoCallback)));
+                self.take_callback_panic();
+                panic::resume_unwind(payload);
+            },
+        };
+        self.unset_callback()?;
+        Ok(ret)
+    }
+
+    /// Registers the callback for all jobs created from this codec's clips. Outstanding jobs are
+    /// flushed first so that they complete on the previous callback. The SDK keeps a reference to the
+    /// callback for as long as it needs it, so it may be kept for the codec's whole lifetime.
+    ///
+    /// Jobs created with a handler, such as those from `Clip::create_job_read_frame_with`, complete
+    /// on their handler instead.
+    pub fn set_callback(&mut self, callback: Arc<Mutex<dyn Callback + Send>>) -> Result<(), Error> {
+        self.flush_jobs()?;
+        self.register_callback(callback)
+    }
+
+    /// Flushes outstanding jobs and unregisters the callback. If the callback panicked, the panic
+    /// is resumed once it has been unregistered.
+    pub fn unset_callback(&mut self) -> Result<(), Error> {
         unsafe {
-            void_result(blackmagic_raw_job_submit(self.implementation))
+            void_result(blackmagic_raw_flush_jobs(self.implementation), "IBlackmagicRaw::FlushJobs")?;
         }
+        let result = self.register_callback(Arc::new(Mutex::new(NoCallback)));
+        self.resume_callback_panic();
+        result
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct ResourceFormat(pub u32);
+    fn register_callback(&mut self, callback: Arc<Mutex<dyn Callback + Send>>) -> Result<(), Error> {
+        let context = Box::new(CallbackContext{
+            callback: callback,
+            panic: self.callback_panic.clone(),
+        });
+        unsafe {
+            let callback = create_blackmagic_raw_callback(Box::into_raw(context) as *mut c_void);
+            let result = void_result(blackmagic_raw_set_callback(self.implementation, callback), "IBlackmagicRaw::SetCallback");
+            blackmagic_raw_unknown_release(callback as *mut IUnknown);
+            result
+        }
+    }
 
-impl ResourceFormat {
-    pub const FORMAT_RGBAU8: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBAU8);
-    pub const FORMAT_BGRAU8: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatBGRAU8);
-    pub const FORMAT_RGBU16: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBU16);
-    pub const FORMAT_RGBAU16: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBAU16);
-    pub const FORMAT_BGRAU16: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatBGRAU16);
-    pub const FORMAT_RGBU16_PLANAR: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBU16Planar);
-    pub const FORMAT_RGBF32: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBF32);
-    pub const FORMAT_RGBF32_PLANAR: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBF32Planar);
-    pub const FORMAT_BGRAF32: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatBGRAF32);
+    /// Waits for all submitted jobs to complete. If a callback panicked while handling one of them,
+    /// the panic is resumed on the calling thread.
+    pub fn flush_jobs(&mut self) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_flush_jobs(self.implementation), "IBlackmagicRaw::FlushJobs")?;
+        }
+        self.resume_callback_panic();
+        Ok(())
+    }
+
+    /// Returns the payload of a panic raised by the callback, if any. While a panic is pending, the
+    /// callback is not invoked and completed jobs are simply released.
+    pub fn take_callback_panic(&mut self) -> Option<Box<dyn Any + Send>> {
+        self.callback_panic.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    fn resume_callback_panic(&mut self) {
+        if let Some(payload) = self.take_callback_panic() {
+            panic::resume_unwind(payload);
+        }
+    }
+
+    /// Returns the codec's configuration. The SDK reads the configuration when the first clip is
+    /// opened, so changes made after that have no effect.
+    pub fn get_configuration(&mut self) -> Result<Configuration, Error> {
+        unsafe {
+            let iface = query_interface::<IBlackmagicRawConfiguration>(self.implementation as *mut IUnknown, REFIID::new([0xF8,0x58,0x8A,0x3D,0xE3,0x1F,0x45,0xBD,0x96,0xC7,0xA5,0x64,0x0E,0xA8,0xB8,0xE7]))?;
+            iface.map(|iface| Configuration{
+                implementation: iface,
+            }).ok_or(Error::new(ErrorCode::NoInterface, "IUnknown::QueryInterface"))
+        }
+    }
+
+    /// Returns the codec's extended configuration. Like `get_configuration`, changes only take
+    /// effect if they're made before the first clip is opened.
+    pub fn get_configuration_ex(&mut self) -> Result<ConfigurationEx, Error> {
+        unsafe {
+            let iface = query_interface::<IBlackmagicRawConfigurationEx>(self.implementation as *mut IUnknown, REFIID::new([0xAC,0xE9,0x07,0x8F,0xAB,0xA0,0x4B,0x26,0xA9,0x54,0xED,0xA1,0x08,0xDA,0xDA,0x5A]))?;
+            iface.map(|iface| ConfigurationEx{
+                implementation: iface,
+            }).ok_or(Error::new(ErrorCode::NoInterface, "IUnknown::QueryInterface"))
+        }
+    }
+
+    /// Returns the per-camera ranges and lists of supported processing attribute values.
+    pub fn get_constants(&mut self) -> Result<Constants, Error> {
+        unsafe {
+            let iface = query_interface::<IBlackmagicRawConstants>(self.implementation as *mut IUnknown, REFIID::new([0x54,0x21,0x00,0x27,0xFA,0x67,0x4E,0xEC,0x9F,0xF6,0xBE,0x78,0x19,0x45,0x10,0x4E]))?;
+            iface.map(|iface| Constants{
+                implementation: iface,
+            }).ok_or(Error::new(ErrorCode::NoInterface, "IUnknown::QueryInterface"))
+        }
+    }
+
+    /// Returns the tone curve helper, which looks up default tone curves and evaluates them.
+    pub fn get_tone_curve(&mut self) -> Result<ToneCurve, Error> {
+        unsafe {
+            let iface = query_interface::<IBlackmagicRawToneCurve>(self.implementation as *mut IUnknown, REFIID::new([0x7E,0x40,0xC1,0x3D,0x35,0x75,0x46,0xB5,0xB2,0xB7,0x85,0xDA,0xE1,0xEE,0xFD,0x77]))?;
+            iface.map(|iface| ToneCurve{
+                implementation: iface,
+            }).ok_or(Error::new(ErrorCode::NoInterface, "IUnknown::QueryInterface"))
+        }
+    }
 }
 
-pub struct Frame {
-    implementation: *mut IBlackmagicRawFrame,
+/// Creates a codec and applies configuration to it before it's returned. Use
+/// `Factory::codec_builder` to create one.
+pub struct CodecBuilder<'a> {
+    factory: &'a Factory,
+    cpu_threads: Option<u32>,
+    pipeline: Option<Pipeline>,
+    device: Option<&'a PipelineDevice>,
+    write_metadata_per_frame: Option<bool>,
+    instruction_set: Option<InstructionSet>,
+    resource_manager: Option<&'a ResourceManagerHandle>,
 }
 
-unsafe impl Send for Frame {}
+impl<'a> CodecBuilder<'a> {
+    /// Limits the number of CPU threads the codec uses. Zero selects the SDK default.
+    pub fn cpu_threads(mut self, thread_count: u32) -> Self {
+        self.cpu_threads = Some(thread_count);
+        self
+    }
 
-impl Drop for Frame {
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Uses the pipeline and instruction set of the given device. This is applied before any
+    /// explicit pipeline.
+    pub fn device(mut self, device: &'a PipelineDevice) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    pub fn write_metadata_per_frame(mut self, write_per_frame: bool) -> Self {
+        self.write_metadata_per_frame = Some(write_per_frame);
+        self
+    }
+
+    /// Forces a specific CPU instruction set. This is applied after the device and pipeline.
+    pub fn instruction_set(mut self, instruction_set: InstructionSet) -> Self {
+        self.instruction_set = Some(instruction_set);
+        self
+    }
+
+    /// Replaces the codec's default resource manager. This is applied after the pipeline, since
+    /// changing the pipeline re-creates the default resource manager.
+    pub fn resource_manager(mut self, resource_manager: &'a ResourceManagerHandle) -> Self {
+        self.resource_manager = Some(resource_manager);
+        self
+    }
+
+    pub fn build(self) -> Result<Codec, Error> {
+        let mut codec = self.factory.create_codec()?;
+        {
+            let mut config = codec.get_configuration()?;
+            if let Some(device) = self.device {
+                config.set_from_device(device)?;
+            }
+            if let Some(pipeline) = self.pipeline {
+                config.set_pipeline(pipeline)?;
+            }
+            if let Some(thread_count) = self.cpu_threads {
+                config.set_cpu_threads(thread_count)?;
+            }
+            if let Some(write_per_frame) = self.write_metadata_per_frame {
+                config.set_write_metadata_per_frame(write_per_frame)?;
+            }
+        }
+        if self.instruction_set.is_some() || self.resource_manager.is_some() {
+            let mut config = codec.get_configuration_ex()?;
+            if let Some(instruction_set) = self.instruction_set {
+                config.set_instruction_set(instruction_set)?;
+            }
+            if let Some(resource_manager) = self.resource_manager {
+                config.set_resource_manager(Some(resource_manager))?;
+            }
+        }
+        Ok(codec)
+    }
+}
+
+pub struct Configuration {
+    implementation: *mut IBlackmagicRawConfiguration,
+}
+
+unsafe impl Send for Configuration {}
+
+impl Drop for Configuration {
     fn drop(&mut self) {
         unsafe {
             blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
@@ -363,44 +933,139 @@ impl Drop for Frame {
     }
 }
 
-impl Frame {
-    unsafe fn new_ref(frame: *mut IBlackmagicRawFrame) -> Frame {
-        blackmagic_raw_unknown_add_ref(frame as *mut IUnknown);
-        Frame{
-            implementation: frame,
+impl Configuration {
+    /// Changing the pipeline causes the default resource manager to be re-created. GPU pipelines
+    /// are given a null context and command queue.
+    pub fn set_pipeline(&mut self, pipeline: Pipeline) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_configuration_set_pipeline(self.implementation, pipeline.0), "IBlackmagicRawConfiguration::SetPipeline")
         }
     }
 
-    pub fn set_resource_format(&mut self, format: ResourceFormat) -> Result<(), Error> {
+    pub fn get_pipeline(&mut self) -> Result<Pipeline, Error> {
+        let mut ret = 0;
         unsafe {
-            void_result(blackmagic_raw_frame_set_resource_format(self.implementation, format.0))
+            void_result(blackmagic_raw_configuration_get_pipeline(self.implementation, &mut ret), "IBlackmagicRawConfiguration::GetPipeline")?;
         }
+        return Ok(Pipeline(ret))
     }
 
-    pub fn create_job_decode_and_process_frame(&mut self, clip_processing_attributes: Option<ClipProcessingAttributes>, frame_processing_attributes: Option<FrameProcessingAttributes>) -> Result<Job, Error> {
-        let mut job: *mut IBlackmagicRawJob = std::ptr::null_mut();
+    /// Checks that the hardware and libraries needed by the pipeline are available.
+    pub fn is_pipeline_supported(&mut self, pipeline: Pipeline) -> Result<bool, Error> {
+        let mut ret = false;
         unsafe {
-            void_result(blackmagic_raw_frame_create_job_decode_and_process_frame(self.implementation, match clip_processing_attributes {
-                Some(obj) => obj.implementation,
-                None => std::ptr::null_mut(),
-            }, match frame_processing_attributes {
+            void_result(blackmagic_raw_configuration_is_pipeline_supported(self.implementation, pipeline.0, &mut ret), "IBlackmagicRawConfiguration::IsPipelineSupported")?;
+        }
+        return Ok(ret)
+    }
+
+    /// Sets the number of CPU threads used by the codec. Zero selects the SDK default.
+    pub fn set_cpu_threads(&mut self, thread_count: u32) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_configuration_set_cpu_threads(self.implementation, thread_count), "IBlackmagicRawConfiguration::SetCPUThreads")
+        }
+    }
+
+    pub fn get_cpu_threads(&mut self) -> Result<u32, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_configuration_get_cpu_threads(self.implementation, &mut ret), "IBlackmagicRawConfiguration::GetCPUThreads")?;
+        }
+        return Ok(ret)
+    }
+
+    pub fn get_max_cpu_thread_count(&mut self) -> Result<u32, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_configuration_get_max_cpu_thread_count(self.implementation, &mut ret), "IBlackmagicRawConfiguration::GetMaxCPUThreadCount")?;
+        }
+        return Ok(ret)
+    }
+
+    /// If true, frame metadata is written only to the frame it belongs to.
+    pub fn set_write_metadata_per_frame(&mut self, write_per_frame: bool) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_configuration_set_write_metadata_per_frame(self.implementation, write_per_frame), "IBlackmagicRawConfiguration::SetWriteMetadataPerFrame")
+        }
+    }
+
+    pub fn get_write_metadata_per_frame(&mut self) -> Result<bool, Error> {
+        let mut ret = false;
+        unsafe {
+            void_result(blackmagic_raw_configuration_get_write_metadata_per_frame(self.implementation, &mut ret), "IBlackmagicRawConfiguration::GetWriteMetadataPerFrame")?;
+        }
+        return Ok(ret)
+    }
+
+    /// Uses the pipeline and instruction set of the given device.
+    pub fn set_from_device(&mut self, device: &PipelineDevice) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_configuration_set_from_device(self.implementation, device.implementation), "IBlackmagicRawConfiguration::SetFromDevice")
+        }
+    }
+}
+
+pub struct ConfigurationEx {
+    implementation: *mut IBlackmagicRawConfigurationEx,
+}
+
+unsafe impl Send for ConfigurationEx {}
+
+impl Drop for ConfigurationEx {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl ConfigurationEx {
+    /// Returns the instruction set the codec is currently using.
+    pub fn get_instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_configuration_ex_get_instruction_set(self.implementation, &mut ret), "IBlackmagicRawConfigurationEx::GetInstructionSet")?;
+        }
+        return Ok(InstructionSet(ret))
+    }
+
+    pub fn set_instruction_set(&mut self, instruction_set: InstructionSet) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_configuration_ex_set_instruction_set(self.implementation, instruction_set.0), "IBlackmagicRawConfigurationEx::SetInstructionSet")
+        }
+    }
+
+    pub fn get_resource_manager(&mut self) -> Result<ResourceManagerHandle, Error> {
+        let mut iface: *mut IBlackmagicRawResourceManager = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_configuration_ex_get_resource_manager(self.implementation, &mut iface), "IBlackmagicRawConfigurationEx::GetResourceManager")?;
+        }
+        return Ok(ResourceManagerHandle{
+            implementation: iface,
+        })
+    }
+
+    /// Replaces the codec's resource manager. Passing `None` restores the default one.
+    pub fn set_resource_manager(&mut self, resource_manager: Option<&ResourceManagerHandle>) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_configuration_ex_set_resource_manager(self.implementation, match resource_manager {
                 Some(obj) => obj.implementation,
                 None => std::ptr::null_mut(),
-            }, &mut job))?;
+            }), "IBlackmagicRawConfigurationEx::SetResourceManager")
         }
-        return Ok(Job{
-            implementation: job,
-        });
     }
 }
 
-pub struct ProcessedImage {
-    implementation: *mut IBlackmagicRawProcessedImage,
+/// A reference to a resource manager object. The codec keeps its own reference once the
+/// handle is installed, so the handle can be dropped afterwards.
+pub struct ResourceManagerHandle {
+    implementation: *mut IBlackmagicRawResourceManager,
 }
 
-unsafe impl Send for ProcessedImage {}
+unsafe impl Send for ResourceManagerHandle {}
+unsafe impl Sync for ResourceManagerHandle {}
 
-impl Drop for ProcessedImage {
+impl Drop for ResourceManagerHandle {
     fn drop(&mut self) {
         unsafe {
             blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
@@ -408,74 +1073,1619 @@ impl Drop for ProcessedImage {
     }
 }
 
-impl ProcessedImage {
-    unsafe fn new_ref(img: *mut IBlackmagicRawProcessedImage) -> ProcessedImage {
-        blackmagic_raw_unknown_add_ref(img as *mut IUnknown);
-        ProcessedImage{
-            implementation: img,
+impl ResourceManagerHandle {
+    /// Wraps a Rust resource manager so that it can be installed on a codec.
+    pub fn new<T: ResourceManager + 'static>(resource_manager: T) -> ResourceManagerHandle {
+        let resource_manager: Box<Box<dyn ResourceManager>> = Box::new(Box::new(resource_manager));
+        unsafe {
+            ResourceManagerHandle{
+                implementation: create_blackmagic_raw_resource_manager(Box::into_raw(resource_manager) as *mut c_void),
+            }
+        }
+    }
+}
+
+/// A resource manager implemented in Rust. The SDK uses it to allocate the buffers that back
+/// decoded and processed images. Only CPU buffers are handled by the default method
+/// implementations.
+///
+/// # Safety
+///
+/// `create_resource` must return a pointer to at least `size_bytes` of memory that stays valid
+/// until it's passed to `release_resource`. The methods may be called concurrently from SDK
+/// threads.
+pub unsafe trait ResourceManager: Send + Sync {
+    fn create_resource(&self, size_bytes: u32, resource_type: ResourceType, usage: ResourceUsage) -> Result<*mut c_void, Error>;
+
+    fn release_resource(&self, resource: *mut c_void, resource_type: ResourceType) -> Result<(), Error>;
+
+    /// This is a utility for callers and isn't used by the SDK itself.
+    ///
+    /// # Safety
+    ///
+    /// `source` and `destination` must be resources of the given types that are each valid for at
+    /// least `size_bytes`.
+    unsafe fn copy_resource(&self, source: *mut c_void, source_type: ResourceType, destination: *mut c_void, destination_type: ResourceType, size_bytes: u32, _copy_async: bool) -> Result<(), Error> {
+        if source_type != ResourceType::TYPE_BUFFER_CPU || destination_type != ResourceType::TYPE_BUFFER_CPU {
+            return Err(Error::new(ErrorCode::NotImpl, "IBlackmagicRawResourceManager::CopyResource"));
+        }
+        std::ptr::copy(source as *const u8, destination as *mut u8, size_bytes as usize);
+        Ok(())
+    }
+
+    /// This is a utility for callers and isn't used by the SDK itself.
+    fn get_resource_host_pointer(&self, resource: *mut c_void, resource_type: ResourceType) -> Result<*mut c_void, Error> {
+        match resource_type {
+            ResourceType::TYPE_BUFFER_CPU => Ok(resource),
+            _ => Err(Error::new(ErrorCode::NotImpl, "IBlackmagicRawResourceManager::GetResourceHostPointer")),
+        }
+    }
+}
+
+fn hresult<T>(result: std::thread::Result<Result<T, Error>>, out: impl FnOnce(T)) -> HRESULT {
+    match result {
+        Ok(Ok(v)) => {
+            out(v);
+            0
+        },
+        Ok(Err(err)) => err.code().unwrap_or(ErrorCode::Fail).hresult(),
+        Err(_) => ErrorCode::Fail.hresult(),
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn resource_manager_create_resource(implementation: *mut Box<dyn ResourceManager>, size_bytes: u32, resource_type: BlackmagicRawResourceType, usage: BlackmagicRawResourceUsage, resource: *mut *mut c_void) -> HRESULT {
+    let implementation = &*implementation;
+    hresult(panic::catch_unwind(AssertUnwindSafe(|| implementation.create_resource(size_bytes, ResourceType(resource_type), ResourceUsage(usage)))), |v| *resource = v)
+}
+
+#[no_mangle]
+unsafe extern "C" fn resource_manager_release_resource(implementation: *mut Box<dyn ResourceManager>, resource: *mut c_void, resource_type: BlackmagicRawResourceType) -> HRESULT {
+    let implementation = &*implementation;
+    hresult(panic::catch_unwind(AssertUnwindSafe(|| implementation.release_resource(resource, ResourceType(resource_type)))), |_| {})
+}
+
+#[no_mangle]
+unsafe extern "C" fn resource_manager_copy_resource(implementation: *mut Box<dyn ResourceManager>, source: *mut c_void, source_type: BlackmagicRawResourceType, destination: *mut c_void, destination_type: BlackmagicRawResourceType, size_bytes: u32, copy_async: bool) -> HRESULT {
+    let implementation = &*implementation;
+    hresult(panic::catch_unwind(AssertUnwindSafe(|| implementation.copy_resource(source, ResourceType(source_type), destination, ResourceType(destination_type), size_bytes, copy_async))), |_| {})
+}
+
+#[no_mangle]
+unsafe extern "C" fn resource_manager_get_resource_host_pointer(implementation: *mut Box<dyn ResourceManager>, resource: *mut c_void, resource_type: BlackmagicRawResourceType, host_pointer: *mut *mut c_void) -> HRESULT {
+    let implementation = &*implementation;
+    hresult(panic::catch_unwind(AssertUnwindSafe(|| implementation.get_resource_host_pointer(resource, ResourceType(resource_type)))), |v| *host_pointer = v)
+}
+
+#[no_mangle]
+unsafe extern "C" fn resource_manager_drop(implementation: *mut Box<dyn ResourceManager>) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(implementation))));
+}
+
+/// A CPU resource manager that keeps released buffers around for reuse instead of returning them
+/// to the system allocator. Buffers are aligned to `PooledResourceManager::ALIGNMENT` bytes.
+///
+/// Dropping the pool frees the pooled buffers, but buffers that haven't been released yet are
+/// leaked rather than freed, since the SDK may still be using them.
+pub struct PooledResourceManager {
+    max_pooled_bytes: usize,
+    state: Mutex<PoolState>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    // Buffers that are ready for reuse, keyed by size.
+    free: HashMap<usize, Vec<usize>>,
+    free_bytes: usize,
+    // Sizes of buffers that are currently handed out to the SDK, keyed by address.
+    outstanding: HashMap<usize, usize>,
+}
+
+impl PooledResourceManager {
+    pub const ALIGNMENT: usize = 64;
+
+    /// Creates a pool that holds on to at most `max_pooled_bytes` of released buffers. Buffers
+    /// released beyond that are freed immediately.
+    pub fn new(max_pooled_bytes: usize) -> PooledResourceManager {
+        PooledResourceManager{
+            max_pooled_bytes: max_pooled_bytes,
+            state: Mutex::new(PoolState::default()),
+        }
+    }
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(std::cmp::max(size, 1), PooledResourceManager::ALIGNMENT).expect("invalid resource layout")
+    }
+}
+
+impl Default for PooledResourceManager {
+    /// Creates a pool that holds on to at most 1 GiB of released buffers.
+    fn default() -> PooledResourceManager {
+        PooledResourceManager::new(1 << 30)
+    }
+}
+
+impl Drop for PooledResourceManager {
+    fn drop(&mut self) {
+        let state = match self.state.get_mut() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        for (size, buffers) in state.free.drain() {
+            for ptr in buffers {
+                unsafe {
+                    dealloc(ptr as *mut u8, PooledResourceManager::layout(size));
+                }
+            }
+        }
+    }
+}
+
+unsafe impl ResourceManager for PooledResourceManager {
+    fn create_resource(&self, size_bytes: u32, resource_type: ResourceType, _usage: ResourceUsage) -> Result<*mut c_void, Error> {
+        if resource_type != ResourceType::TYPE_BUFFER_CPU {
+            return Err(Error::new(ErrorCode::NotImpl, "IBlackmagicRawResourceManager::CreateResource"));
+        }
+        let size = size_bytes as usize;
+        let mut state = self.state.lock().map_err(|_| Error::new(ErrorCode::Fail, "IBlackmagicRawResourceManager::CreateResource"))?;
+        let reused = state.free.get_mut(&size).and_then(|buffers| buffers.pop());
+        let ptr = match reused {
+            Some(ptr) => {
+                state.free_bytes -= size;
+                ptr
+            },
+            None => {
+                let ptr = unsafe { alloc(PooledResourceManager::layout(size)) };
+                if ptr.is_null() {
+                    return Err(Error::new(ErrorCode::OutOfMemory, "IBlackmagicRawResourceManager::CreateResource"));
+                }
+                ptr as usize
+            },
+        };
+        state.outstanding.insert(ptr, size);
+        Ok(ptr as *mut c_void)
+    }
+
+    fn release_resource(&self, resource: *mut c_void, resource_type: ResourceType) -> Result<(), Error> {
+        if resource_type != ResourceType::TYPE_BUFFER_CPU {
+            return Err(Error::new(ErrorCode::NotImpl, "IBlackmagicRawResourceManager::ReleaseResource"));
+        }
+        let mut state = self.state.lock().map_err(|_| Error::new(ErrorCode::Fail, "IBlackmagicRawResourceManager::ReleaseResource"))?;
+        let size = state.outstanding.remove(&(resource as usize)).ok_or(Error::new(ErrorCode::InvalidArg, "IBlackmagicRawResourceManager::ReleaseResource"))?;
+        if state.free_bytes + size <= self.max_pooled_bytes {
+            state.free_bytes += size;
+            state.free.entry(size).or_insert_with(Vec::new).push(resource as usize);
+        } else {
+            unsafe {
+                dealloc(resource as *mut u8, PooledResourceManager::layout(size));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Clip {
+    implementation: *mut IBlackmagicRawClip,
+}
+
+unsafe impl Send for Clip {}
+
+impl Drop for Clip {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl Clip {
+    unsafe fn new_ref(clip: *mut IBlackmagicRawClip) -> Clip {
+        blackmagic_raw_unknown_add_ref(clip as *mut IUnknown);
+        Clip{
+            implementation: clip,
+        }
+    }
+
+    /// Returns the camera type, which is used to look up the camera's supported attribute values
+    /// via `Constants`.
+    pub fn get_camera_type(&mut self) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_clip_get_camera_type(self.implementation, &mut buf), "IBlackmagicRawClip::GetCameraType")?;
+            Ok(buffer_to_string(buf))
         }
     }
 
     pub fn get_width(&mut self) -> Result<u32, Error> {
-        let mut out = 0;
+        let mut width = 0;
+        unsafe {
+            void_result(blackmagic_raw_clip_get_width(self.implementation, &mut width), "IBlackmagicRawClip::GetWidth")?;
+        }
+        return Ok(width)
+    }
+
+    pub fn get_height(&mut self) -> Result<u32, Error> {
+        let mut height = 0;
+        unsafe {
+            void_result(blackmagic_raw_clip_get_height(self.implementation, &mut height), "IBlackmagicRawClip::GetHeight")?;
+        }
+        return Ok(height)
+    }
+
+    pub fn get_frame_rate(&mut self) -> Result<f32, Error> {
+        let mut frame_rate = 0.0;
+        unsafe {
+            void_result(blackmagic_raw_clip_get_frame_rate(self.implementation, &mut frame_rate), "IBlackmagicRawClip::GetFrameRate")?;
+        }
+        return Ok(frame_rate)
+    }
+
+    pub fn get_frame_count(&mut self) -> Result<u64, Error> {
+        let mut frame_count = 0;
+        unsafe {
+            void_result(blackmagic_raw_clip_get_frame_count(self.implementation, &mut frame_count), "IBlackmagicRawClip::GetFrameCount")?;
+        }
+        return Ok(frame_count)
+    }
+
+    /// Returns the timecode of a frame, formatted as "HH:MM:SS:FF". See `timecode_for_frame` for a
+    /// parsed version.
+    pub fn get_timecode_for_frame(&mut self, frame_index: u64) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_clip_get_timecode_for_frame(self.implementation, frame_index, &mut buf), "IBlackmagicRawClip::GetTimecodeForFrame")?;
+            Ok(buffer_to_string(buf))
+        }
+    }
+
+    /// Returns the timecode of the clip's first frame as a number of frames since midnight, and
+    /// whether the timecode is drop-frame. This is `None` if the SDK doesn't support the query.
+    pub fn query_timecode_info(&mut self) -> Result<Option<(u32, bool)>, Error> {
+        unsafe {
+            let clip_ex = match self.query_interface::<IBlackmagicRawClipEx>(REFIID::new([0xD2,0x60,0xC7,0xD0,0x93,0xBD,0x4D,0x68,0xB6,0x00,0x93,0xB4,0xCA,0xB7,0xF8,0x70]))? {
+                Some(clip_ex) => clip_ex,
+                None => return Ok(None),
+            };
+            let mut base_frame_index = 0;
+            let mut is_drop_frame = false;
+            let result = void_result(blackmagic_raw_clip_ex_query_timecode_info(clip_ex, &mut base_frame_index, &mut is_drop_frame), "IBlackmagicRawClipEx::QueryTimecodeInfo");
+            blackmagic_raw_unknown_release(clip_ex as *mut IUnknown);
+            result?;
+            Ok(Some((base_frame_index, is_drop_frame)))
+        }
+    }
+
+    pub fn get_metadata_iterator(&mut self) -> Result<MetadataIterator, Error> {
+        let mut iface: *mut IBlackmagicRawMetadataIterator = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_clip_get_metadata_iterator(self.implementation, &mut iface), "IBlackmagicRawClip::GetMetadataIterator")?;
+        }
+        return Ok(MetadataIterator{
+            implementation: iface,
+        })
+    }
+
+    /// Returns the value of a single metadata key, or `None` if the key isn't set or its type can't
+    /// be represented.
+    pub fn get_metadata(&mut self, key: &str) -> Result<Option<Value>, Error> {
+        let c_key = c_string(key, "IBlackmagicRawClip::GetMetadata")?;
+        unsafe {
+            get_variant(|value| blackmagic_raw_clip_get_metadata(self.implementation, c_key.as_ptr(), value), "IBlackmagicRawClip::GetMetadata")
+        }
+    }
+
+    /// Overrides a metadata value for the clip, such as "scene", "take", or "good_take", which is a
+    /// `UInt8` flag. The change is only written to disk by `save_sidecar_file`.
+    pub fn set_metadata(&mut self, key: &str, value: &Value) -> Result<(), Error> {
+        let c_key = c_string(key, "IBlackmagicRawClip::SetMetadata")?;
+        let mut value = VariantArg::new(value, "IBlackmagicRawClip::SetMetadata")?;
+        unsafe {
+            void_result(blackmagic_raw_clip_set_metadata(self.implementation, c_key.as_ptr(), &mut value.variant), "IBlackmagicRawClip::SetMetadata")
+        }
+    }
+
+    /// Reverts a metadata value to the one recorded in the clip.
+    pub fn clear_metadata(&mut self, key: &str) -> Result<(), Error> {
+        let c_key = c_string(key, "IBlackmagicRawClip::SetMetadata")?;
+        unsafe {
+            void_result(blackmagic_raw_clip_set_metadata(self.implementation, c_key.as_ptr(), std::ptr::null_mut()), "IBlackmagicRawClip::SetMetadata")
+        }
+    }
+
+    /// Returns whether a sidecar file was found and parsed when the clip was opened.
+    pub fn get_sidecar_file_attached(&mut self) -> Result<bool, Error> {
+        let mut ret = false;
+        unsafe {
+            void_result(blackmagic_raw_clip_get_sidecar_file_attached(self.implementation, &mut ret), "IBlackmagicRawClip::GetSidecarFileAttached")?;
+        }
+        return Ok(ret)
+    }
+
+    /// Writes the clip and frame metadata overrides to the clip's .sidecar file.
+    pub fn save_sidecar_file(&mut self) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_clip_save_sidecar_file(self.implementation), "IBlackmagicRawClip::SaveSidecarFile")
+        }
+    }
+
+    /// Discards unsaved metadata overrides and reads the clip's .sidecar file again. Parse problems
+    /// are reported to the codec's callback.
+    pub fn reload_sidecar_file(&mut self) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_clip_reload_sidecar_file(self.implementation), "IBlackmagicRawClip::ReloadSidecarFile")
+        }
+    }
+
+    /// Returns a copy of the clip's current processing attributes, which can be modified and passed
+    /// to jobs.
+    pub fn clone_clip_processing_attributes(&mut self) -> Result<ClipProcessingAttributes, Error> {
+        let mut attributes: *mut IBlackmagicRawClipProcessingAttributes = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_clip_clone_clip_processing_attributes(self.implementation, &mut attributes), "IBlackmagicRawClip::CloneClipProcessingAttributes")?;
+        }
+        return Ok(ClipProcessingAttributes{
+            implementation: attributes,
+            validation: None,
+        })
+    }
+
+    unsafe fn query_interface<T>(&self, iid: REFIID) -> Result<Option<*mut T>, Error> {
+        query_interface(self.implementation as *mut IUnknown, iid)
+    }
+
+    pub fn get_audio(&mut self) -> Result<Option<ClipAudio>, Error> {
+        unsafe {
+            Ok(self.query_interface::<IBlackmagicRawClipAudio>(REFIID::new([0x76,0xD4,0xAC,0xED,0xE0,0xD6,0x45,0xBB,0xB5,0x47,0x56,0xB7,0x43,0x5B,0x2A,0x1D]))?.map(|audio| ClipAudio{
+                implementation: audio,
+            }))
+        }
+    }
+
+    pub fn create_job_read_frame(&mut self, frame: u64) -> Result<Job, Error> {
+        let mut job: *mut IBlackmagicRawJob = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_clip_create_job_read_frame(self.implementation, frame, &mut job), "IBlackmagicRawClip::CreateJobReadFrame")?;
+        }
+        return Ok(Job{
+            implementation: job,
+            owns_handler: false,
+        });
+    }
+
+    /// Creates a read job whose completion is passed to `handler` instead of the codec's callback.
+    pub fn create_job_read_frame_with<F>(&mut self, frame: u64, handler: F) -> Result<Job, Error>
+        where F: FnOnce(Job, Result<Frame, Error>) + Send + 'static
+    {
+        let mut job = self.create_job_read_frame(frame)?;
+        unsafe {
+            job.set_handler(JobHandler::ReadFrame(Box::new(handler)))?;
+        }
+        Ok(job)
+    }
+
+    /// Reads, decodes, and processes a single frame, blocking until it's done. This works regardless
+    /// of the codec's callback, but the codec must use a CPU pipeline.
+    ///
+    /// It can't be called from a callback or job handler, since those run on the SDK threads that
+    /// it would wait for, and fails with `ErrorCode::Unexpected` if it is.
+    pub fn decode_frame(&mut self, frame: u64, format: ResourceFormat, options: DecodeOptions) -> Result<Image, Error> {
+        if in_callback() {
+            return Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawClip::CreateJobReadFrame"));
+        }
+        let (tx, rx) = mpsc::channel();
+        let read_tx = tx.clone();
+        self.create_job_read_frame_with(frame, move |_job, result| {
+            let submitted = result.and_then(|mut frame| {
+                frame.set_resource_format(format)?;
+                if let Some(scale) = options.resolution_scale {
+                    frame.set_resolution_scale(scale)?;
+                }
+                frame.create_job_decode_and_process_frame_with(options.clip_processing_attributes, options.frame_processing_attributes, move |_job, result| {
+                    let _ = tx.send(result.and_then(|mut image| image.to_image()));
+                })?.submit()
+            });
+            if let Err(e) = submitted {
+                let _ = read_tx.send(Err(e));
+            }
+        })?.submit()?;
+        // The senders are dropped without sending if a job is abandoned, for example because another
+        // callback on the codec panicked.
+        rx.recv().unwrap_or_else(|_| Err(Error::new(ErrorCode::Abort, "IBlackmagicRawClip::CreateJobReadFrame")))
+    }
+
+    /// Creates a job that writes frames `frame_index..frame_index + frame_count` to a new clip at
+    /// `path`. The job reports to the codec's callback, which is responsible for removing the
+    /// partial file if it fails.
+    pub fn create_job_trim<P: AsRef<Path>>(&mut self, path: P, frame_index: u64, frame_count: u64, options: TrimOptions) -> Result<Job, Error> {
+        let path = path.as_ref();
+        let mut job: *mut IBlackmagicRawJob = std::ptr::null_mut();
+        let c_path = c_path(path, "IBlackmagicRawClip::CreateJobTrim")?;
+        unsafe {
+            void_result(blackmagic_raw_clip_create_job_trim(self.implementation, c_path.as_ptr(), frame_index, frame_count, match options.clip_processing_attributes {
+                Some(obj) => obj.implementation,
+                None => std::ptr::null_mut(),
+            }, match options.frame_processing_attributes {
+                Some(obj) => obj.implementation,
+                None => std::ptr::null_mut(),
+            }, &mut job), "IBlackmagicRawClip::CreateJobTrim").map_err(|e| e.with_path(path))?;
+        }
+        return Ok(Job{
+            implementation: job,
+            owns_handler: false,
+        });
+    }
+
+    /// Creates a trim job that reports its progress and completion to the given handlers instead of
+    /// the codec's callback. If the job fails or is aborted, the partial file is removed before
+    /// `complete` is called, unless a file was already at `path` when the job was created.
+    pub fn create_job_trim_with<T, P, C>(&mut self, path: T, frame_index: u64, frame_count: u64, options: TrimOptions, progress: P, complete: C) -> Result<Job, Error>
+        where T: AsRef<Path>,
+              P: FnMut(Job, f32) + Send + 'static,
+              C: FnOnce(Job, Result<(), Error>) + Send + 'static,
+    {
+        let output = TrimOutput::new(path.as_ref());
+        let mut job = self.create_job_trim(&output.path, frame_index, frame_count, options)?;
+        unsafe {
+            job.set_handler(JobHandler::Trim{
+                progress: Box::new(progress),
+                complete: Box::new(move |job, result: Result<(), Error>| {
+                    let result = output.finish(result);
+                    complete(job, result)
+                }),
+            })?;
+        }
+        Ok(job)
+    }
+}
+
+// The destination of a trim job. A file that was already at the path before the job was created
+// is never removed, since the SDK may have refused to overwrite it.
+struct TrimOutput {
+    path: PathBuf,
+    existed: bool,
+}
+
+impl TrimOutput {
+    fn new(path: &Path) -> TrimOutput {
+        TrimOutput{
+            path: path.to_path_buf(),
+            existed: path.exists(),
+        }
+    }
+
+    // Removes the partial file if the job failed, and adds the path to any error.
+    fn finish(&self, result: Result<(), Error>) -> Result<(), Error> {
+        if result.is_err() && !self.existed {
+            let _ = std::fs::remove_file(&self.path);
+        }
+        result.map_err(|e| e.with_path(&self.path))
+    }
+}
+
+pub struct ClipAudio {
+    implementation: *mut IBlackmagicRawClipAudio,
+}
+
+unsafe impl Send for ClipAudio {}
+
+impl Drop for ClipAudio {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl ClipAudio {
+    pub fn get_channel_count(&mut self) -> Result<u32, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_clip_audio_get_channel_count(self.implementation, &mut ret), "IBlackmagicRawClipAudio::GetAudioChannelCount")?;
+        }
+        return Ok(ret)
+    }
+
+    pub fn get_sample_rate(&mut self) -> Result<u32, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_clip_audio_get_sample_rate(self.implementation, &mut ret), "IBlackmagicRawClipAudio::GetAudioSampleRate")?;
+        }
+        return Ok(ret)
+    }
+
+    pub fn get_sample_count(&mut self) -> Result<u64, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_clip_audio_get_sample_count(self.implementation, &mut ret), "IBlackmagicRawClipAudio::GetAudioSampleCount")?;
+        }
+        return Ok(ret)
+    }
+}
+
+pub struct Job {
+    implementation: *mut IBlackmagicRawJob,
+    // Set while the job holds a handler that won't be consumed by a completion because the job
+    // hasn't been submitted.
+    owns_handler: bool,
+}
+
+unsafe impl Send for Job {}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe {
+            if self.owns_handler {
+                drop(take_job_handler(self.implementation));
+            }
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl Job {
+    unsafe fn new_ref(job: *mut IBlackmagicRawJob) -> Job {
+        blackmagic_raw_unknown_add_ref(job as *mut IUnknown);
+        Job{
+            implementation: job,
+            owns_handler: false,
+        }
+    }
+
+    unsafe fn set_handler(&mut self, handler: JobHandler) -> Result<(), Error> {
+        let handler = Box::into_raw(Box::new(handler));
+        if let Err(e) = void_result(blackmagic_raw_job_set_user_data(self.implementation, handler as *mut c_void), "IBlackmagicRawJob::SetUserData") {
+            drop(Box::from_raw(handler));
+            return Err(e);
+        }
+        self.owns_handler = true;
+        Ok(())
+    }
+
+    pub fn submit(&mut self) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_job_submit(self.implementation), "IBlackmagicRawJob::Submit")?;
+        }
+        self.owns_handler = false;
+        Ok(())
+    }
+
+    /// Requests that the job be cancelled. The job still completes, typically with `ErrorCode::Abort`.
+    pub fn abort(&mut self) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_job_abort(self.implementation), "IBlackmagicRawJob::Abort")
+        }
+    }
+}
+
+/// A completion handler stored in a job's user data.
+enum JobHandler {
+    ReadFrame(Box<dyn FnOnce(Job, Result<Frame, Error>) + Send>),
+    DecodeAndProcessFrame(Box<dyn FnOnce(Job, Result<ProcessedImage, Error>) + Send>),
+    Trim{
+        progress: Box<dyn FnMut(Job, f32) + Send>,
+        complete: Box<dyn FnOnce(Job, Result<(), Error>) + Send>,
+    },
+}
+
+unsafe fn job_handler(job: *mut IBlackmagicRawJob) -> *mut JobHandler {
+    let mut handler: *mut c_void = std::ptr::null_mut();
+    if blackmagic_raw_job_get_user_data(job, &mut handler) != 0 {
+        return std::ptr::null_mut();
+    }
+    handler as *mut JobHandler
+}
+
+unsafe fn take_job_handler(job: *mut IBlackmagicRawJob) -> Option<Box<JobHandler>> {
+    let handler = job_handler(job);
+    if handler.is_null() {
+        return None;
+    }
+    blackmagic_raw_job_set_user_data(job, std::ptr::null_mut());
+    Some(Box::from_raw(handler))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceFormat(pub u32);
+
+impl ResourceFormat {
+    pub const FORMAT_RGBAU8: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBAU8);
+    pub const FORMAT_BGRAU8: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatBGRAU8);
+    pub const FORMAT_RGBU16: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBU16);
+    pub const FORMAT_RGBAU16: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBAU16);
+    pub const FORMAT_BGRAU16: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatBGRAU16);
+    pub const FORMAT_RGBU16_PLANAR: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBU16Planar);
+    pub const FORMAT_RGBF32: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBF32);
+    pub const FORMAT_RGBF32_PLANAR: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatRGBF32Planar);
+    pub const FORMAT_BGRAF32: ResourceFormat = ResourceFormat(_BlackmagicRawResourceFormat_blackmagicRawResourceFormatBGRAF32);
+}
+
+/// The resolution a frame is decoded at. Lower scales are much cheaper to decode, which makes them
+/// useful for proxies and thumbnails. The upside-down scales also rotate the image by 180 degrees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolutionScale(pub u32);
+
+impl ResolutionScale {
+    pub const FULL: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleFull);
+    pub const HALF: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleHalf);
+    pub const QUARTER: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleQuarter);
+    pub const EIGHTH: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleEighth);
+    pub const FULL_UPSIDE_DOWN: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleFullUpsideDown);
+    pub const HALF_UPSIDE_DOWN: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleHalfUpsideDown);
+    pub const QUARTER_UPSIDE_DOWN: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleQuarterUpsideDown);
+    pub const EIGHTH_UPSIDE_DOWN: ResolutionScale = ResolutionScale(_BlackmagicRawResolutionScale_blackmagicRawResolutionScaleEighthUpsideDown);
+
+    /// Returns the factor each dimension is divided by, or `None` for unknown scales.
+    pub fn divisor(&self) -> Option<u32> {
+        match *self {
+            ResolutionScale::FULL | ResolutionScale::FULL_UPSIDE_DOWN => Some(1),
+            ResolutionScale::HALF | ResolutionScale::HALF_UPSIDE_DOWN => Some(2),
+            ResolutionScale::QUARTER | ResolutionScale::QUARTER_UPSIDE_DOWN => Some(4),
+            ResolutionScale::EIGHTH | ResolutionScale::EIGHTH_UPSIDE_DOWN => Some(8),
+            _ => None,
+        }
+    }
+
+    pub fn is_upside_down(&self) -> bool {
+        match *self {
+            ResolutionScale::FULL_UPSIDE_DOWN | ResolutionScale::HALF_UPSIDE_DOWN | ResolutionScale::QUARTER_UPSIDE_DOWN | ResolutionScale::EIGHTH_UPSIDE_DOWN => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceType(pub u32);
+
+impl ResourceType {
+    pub const TYPE_BUFFER_CPU: ResourceType = ResourceType(_BlackmagicRawResourceType_blackmagicRawResourceTypeBufferCPU);
+    pub const TYPE_BUFFER_METAL: ResourceType = ResourceType(_BlackmagicRawResourceType_blackmagicRawResourceTypeBufferMetal);
+    pub const TYPE_BUFFER_CUDA: ResourceType = ResourceType(_BlackmagicRawResourceType_blackmagicRawResourceTypeBufferCUDA);
+    pub const TYPE_BUFFER_OPENCL: ResourceType = ResourceType(_BlackmagicRawResourceType_blackmagicRawResourceTypeBufferOpenCL);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceUsage(pub u32);
+
+impl ResourceUsage {
+    pub const USAGE_READ_CPU_WRITE_CPU: ResourceUsage = ResourceUsage(_BlackmagicRawResourceUsage_blackmagicRawResourceUsageReadCPUWriteCPU);
+    pub const USAGE_READ_GPU_WRITE_GPU: ResourceUsage = ResourceUsage(_BlackmagicRawResourceUsage_blackmagicRawResourceUsageReadGPUWriteGPU);
+    pub const USAGE_READ_GPU_WRITE_CPU: ResourceUsage = ResourceUsage(_BlackmagicRawResourceUsage_blackmagicRawResourceUsageReadGPUWriteCPU);
+    pub const USAGE_READ_CPU_WRITE_GPU: ResourceUsage = ResourceUsage(_BlackmagicRawResourceUsage_blackmagicRawResourceUsageReadCPUWriteGPU);
+}
+
+pub struct Frame {
+    implementation: *mut IBlackmagicRawFrame,
+}
+
+unsafe impl Send for Frame {}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl Frame {
+    unsafe fn new_ref(frame: *mut IBlackmagicRawFrame) -> Frame {
+        blackmagic_raw_unknown_add_ref(frame as *mut IUnknown);
+        Frame{
+            implementation: frame,
+        }
+    }
+
+    pub fn get_frame_index(&mut self) -> Result<u64, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_frame_get_frame_index(self.implementation, &mut ret), "IBlackmagicRawFrame::GetFrameIndex")?;
+        }
+        return Ok(ret)
+    }
+
+    /// Returns the frame's timecode, formatted as "HH:MM:SS:FF".
+    pub fn get_timecode(&mut self) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_frame_get_timecode(self.implementation, &mut buf), "IBlackmagicRawFrame::GetTimecode")?;
+            Ok(buffer_to_string(buf))
+        }
+    }
+
+    /// Iterates over the frame's metadata. The codec must be configured to write metadata per frame
+    /// for this to include more than the clip's metadata.
+    pub fn get_metadata_iterator(&mut self) -> Result<MetadataIterator, Error> {
+        let mut iface: *mut IBlackmagicRawMetadataIterator = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_frame_get_metadata_iterator(self.implementation, &mut iface), "IBlackmagicRawFrame::GetMetadataIterator")?;
+        }
+        return Ok(MetadataIterator{
+            implementation: iface,
+        })
+    }
+
+    /// Returns the value of a single metadata key, or `None` if the key isn't set or its type can't
+    /// be represented.
+    pub fn get_metadata(&mut self, key: &str) -> Result<Option<Value>, Error> {
+        let c_key = c_string(key, "IBlackmagicRawFrame::GetMetadata")?;
+        unsafe {
+            get_variant(|value| blackmagic_raw_frame_get_metadata(self.implementation, c_key.as_ptr(), value), "IBlackmagicRawFrame::GetMetadata")
+        }
+    }
+
+    /// Overrides a metadata value for this frame. The change is only written to disk by
+    /// `Clip::save_sidecar_file`.
+    pub fn set_metadata(&mut self, key: &str, value: &Value) -> Result<(), Error> {
+        let c_key = c_string(key, "IBlackmagicRawFrame::SetMetadata")?;
+        let mut value = VariantArg::new(value, "IBlackmagicRawFrame::SetMetadata")?;
+        unsafe {
+            void_result(blackmagic_raw_frame_set_metadata(self.implementation, c_key.as_ptr(), &mut value.variant), "IBlackmagicRawFrame::SetMetadata")
+        }
+    }
+
+    /// Reverts a metadata value for this frame to the one recorded in the clip.
+    pub fn clear_metadata(&mut self, key: &str) -> Result<(), Error> {
+        let c_key = c_string(key, "IBlackmagicRawFrame::SetMetadata")?;
+        unsafe {
+            void_result(blackmagic_raw_frame_set_metadata(self.implementation, c_key.as_ptr(), std::ptr::null_mut()), "IBlackmagicRawFrame::SetMetadata")
+        }
+    }
+
+    /// Returns a copy of the frame's current processing attributes, which can be modified and passed
+    /// to `create_job_decode_and_process_frame`.
+    pub fn clone_frame_processing_attributes(&mut self) -> Result<FrameProcessingAttributes, Error> {
+        let mut attributes: *mut IBlackmagicRawFrameProcessingAttributes = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_frame_clone_frame_processing_attributes(self.implementation, &mut attributes), "IBlackmagicRawFrame::CloneFrameProcessingAttributes")?;
+        }
+        return Ok(FrameProcessingAttributes{
+            implementation: attributes,
+            validation: None,
+        })
+    }
+
+    pub fn set_resource_format(&mut self, format: ResourceFormat) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_frame_set_resource_format(self.implementation, format.0), "IBlackmagicRawFrame::SetResourceFormat")
+        }
+    }
+
+    pub fn get_resource_format(&mut self) -> Result<ResourceFormat, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_frame_get_resource_format(self.implementation, &mut ret), "IBlackmagicRawFrame::GetResourceFormat")?;
+        }
+        return Ok(ResourceFormat(ret))
+    }
+
+    pub fn set_resolution_scale(&mut self, scale: ResolutionScale) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_frame_set_resolution_scale(self.implementation, scale.0), "IBlackmagicRawFrame::SetResolutionScale")
+        }
+    }
+
+    pub fn get_resolution_scale(&mut self) -> Result<ResolutionScale, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_frame_get_resolution_scale(self.implementation, &mut ret), "IBlackmagicRawFrame::GetResolutionScale")?;
+        }
+        return Ok(ResolutionScale(ret))
+    }
+
+    pub fn create_job_decode_and_process_frame(&mut self, clip_processing_attributes: Option<ClipProcessingAttributes>, frame_processing_attributes: Option<FrameProcessingAttributes>) -> Result<Job, Error> {
+        let mut job: *mut IBlackmagicRawJob = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_frame_create_job_decode_and_process_frame(self.implementation, match clip_processing_attributes {
+                Some(obj) => obj.implementation,
+                None => std::ptr::null_mut(),
+            }, match frame_processing_attributes {
+                Some(obj) => obj.implementation,
+                None => std::ptr::null_mut(),
+            }, &mut job), "IBlackmagicRawFrame::CreateJobDecodeAndProcessFrame")?;
+        }
+        return Ok(Job{
+            implementation: job,
+            owns_handler: false,
+        });
+    }
+
+    /// Creates a decode and process job whose completion is passed to `handler` instead of the
+    /// codec's callback.
+    pub fn create_job_decode_and_process_frame_with<F>(&mut self, clip_processing_attributes: Option<ClipProcessingAttributes>, frame_processing_attributes: Option<FrameProcessingAttributes>, handler: F) -> Result<Job, Error>
+        where F: FnOnce(Job, Result<ProcessedImage, Error>) + Send + 'static
+    {
+        let mut job = self.create_job_decode_and_process_frame(clip_processing_attributes, frame_processing_attributes)?;
+        unsafe {
+            job.set_handler(JobHandler::DecodeAndProcessFrame(Box::new(handler)))?;
+        }
+        Ok(job)
+    }
+}
+
+pub struct ProcessedImage {
+    implementation: *mut IBlackmagicRawProcessedImage,
+}
+
+unsafe impl Send for ProcessedImage {}
+
+impl Drop for ProcessedImage {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl ProcessedImage {
+    unsafe fn new_ref(img: *mut IBlackmagicRawProcessedImage) -> ProcessedImage {
+        blackmagic_raw_unknown_add_ref(img as *mut IUnknown);
+        ProcessedImage{
+            implementation: img,
+        }
+    }
+
+    pub fn get_width(&mut self) -> Result<u32, Error> {
+        let mut out = 0;
+        unsafe {
+            void_result(blackmagic_raw_processed_image_get_width(self.implementation, &mut out), "IBlackmagicRawProcessedImage::GetWidth")?
+        }
+        Ok(out)
+    }
+
+    pub fn get_height(&mut self) -> Result<u32, Error> {
+        let mut out = 0;
+        unsafe {
+            void_result(blackmagic_raw_processed_image_get_height(self.implementation, &mut out), "IBlackmagicRawProcessedImage::GetHeight")?
+        }
+        Ok(out)
+    }
+
+    pub fn get_resource_size_bytes(&mut self) -> Result<u32, Error> {
+        let mut out = 0;
+        unsafe {
+            void_result(blackmagic_raw_processed_image_get_resource_size_bytes(self.implementation, &mut out), "IBlackmagicRawProcessedImage::GetResourceSizeBytes")?
+        }
+        Ok(out)
+    }
+
+    pub fn get_resource(&mut self) -> Result<&[u8], Error> {
+        let len = self.get_resource_size_bytes()?;
+        unsafe {
+            let mut buf: *mut c_void = std::ptr::null_mut();
+            void_result(blackmagic_raw_processed_image_get_resource(self.implementation, &mut buf), "IBlackmagicRawProcessedImage::GetResource")?;
+            Ok(std::slice::from_raw_parts(buf as *mut u8, len as usize))
+        }
+    }
+
+    pub fn get_resource_type(&mut self) -> Result<ResourceType, Error> {
+        let mut out = 0;
+        unsafe {
+            void_result(blackmagic_raw_processed_image_get_resource_type(self.implementation, &mut out), "IBlackmagicRawProcessedImage::GetResourceType")?
+        }
+        Ok(ResourceType(out))
+    }
+
+    pub fn get_resource_format(&mut self) -> Result<ResourceFormat, Error> {
+        let mut out = 0;
+        unsafe {
+            void_result(blackmagic_raw_processed_image_get_resource_format(self.implementation, &mut out), "IBlackmagicRawProcessedImage::GetResourceFormat")?
+        }
+        Ok(ResourceFormat(out))
+    }
+
+    /// Copies the image into memory owned by the caller. Only images in CPU memory can be copied.
+    pub fn to_image(&mut self) -> Result<Image, Error> {
+        if self.get_resource_type()? != ResourceType::TYPE_BUFFER_CPU {
+            return Err(Error::new(ErrorCode::NotImpl, "IBlackmagicRawProcessedImage::GetResource"));
+        }
+        Ok(Image{
+            width: self.get_width()?,
+            height: self.get_height()?,
+            format: self.get_resource_format()?,
+            data: self.get_resource()?.to_vec(),
+        })
+    }
+}
+
+/// A decoded and processed frame in memory owned by the caller. `data` is laid out as described by
+/// `format`.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub format: ResourceFormat,
+    pub data: Vec<u8>,
+}
+
+/// Options for `Clip::decode_frame`.
+#[derive(Default)]
+pub struct DecodeOptions {
+    /// Decodes at a reduced resolution. Defaults to full resolution.
+    pub resolution_scale: Option<ResolutionScale>,
+    /// Overrides the clip's processing attributes, such as gamma or tone curve.
+    pub clip_processing_attributes: Option<ClipProcessingAttributes>,
+    /// Overrides the frame's processing attributes, such as white balance or exposure.
+    pub frame_processing_attributes: Option<FrameProcessingAttributes>,
+}
+
+/// Options for `Clip::create_job_trim` and `Clip::trim`.
+#[derive(Default)]
+pub struct TrimOptions {
+    /// Processing attributes to store in the new clip, such as gamma or tone curve.
+    pub clip_processing_attributes: Option<ClipProcessingAttributes>,
+    /// Processing attributes to store in the new clip's frames, such as white balance or exposure.
+    pub frame_processing_attributes: Option<FrameProcessingAttributes>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClipProcessingAttribute(pub u32);
+
+impl ClipProcessingAttribute {
+    pub const COLOR_SCIENCE_GEN: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeColorScienceGen);
+    pub const GAMMA: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeGamma);
+    pub const GAMUT: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeGamut);
+    pub const TONE_CURVE_CONTRAST: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveContrast);
+    pub const TONE_CURVE_SATURATION: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveSaturation);
+    pub const TONE_CURVE_MIDPOINT: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveMidpoint);
+    pub const TONE_CURVE_HIGHLIGHTS: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveHighlights);
+    pub const TONE_CURVE_SHADOWS: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveShadows);
+    pub const TONE_CURVE_VIDEO_BLACK_LEVEL: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveVideoBlackLevel);
+    pub const TONE_CURVE_BLACK_LEVEL: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveBlackLevel);
+    pub const TONE_CURVE_WHITE_LEVEL: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeToneCurveWhiteLevel);
+    pub const HIGHLIGHT_RECOVERY: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeHighlightRecovery);
+    pub const ANALOG_GAIN: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeAnalogGain);
+    pub const POST_3D_LUT_MODE: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributePost3DLUTMode);
+    pub const EMBEDDED_POST_3D_LUT_NAME: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeEmbeddedPost3DLUTName);
+    pub const EMBEDDED_POST_3D_LUT_TITLE: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeEmbeddedPost3DLUTTitle);
+    pub const EMBEDDED_POST_3D_LUT_SIZE: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeEmbeddedPost3DLUTSize);
+    pub const EMBEDDED_POST_3D_LUT_DATA: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeEmbeddedPost3DLUTData);
+    pub const SIDECAR_POST_3D_LUT_NAME: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeSidecarPost3DLUTName);
+    pub const SIDECAR_POST_3D_LUT_TITLE: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeSidecarPost3DLUTTitle);
+    pub const SIDECAR_POST_3D_LUT_SIZE: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeSidecarPost3DLUTSize);
+    pub const SIDECAR_POST_3D_LUT_DATA: ClipProcessingAttribute = ClipProcessingAttribute(_BlackmagicRawClipProcessingAttribute_blackmagicRawClipProcessingAttributeSidecarPost3DLUTData);
+}
+
+impl fmt::Display for ClipProcessingAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClipProcessingAttribute::COLOR_SCIENCE_GEN => write!(f, "color science gen"),
+            ClipProcessingAttribute::GAMMA => write!(f, "gamma"),
+            ClipProcessingAttribute::GAMUT => write!(f, "gamut"),
+            ClipProcessingAttribute::TONE_CURVE_CONTRAST => write!(f, "tone curve contrast"),
+            ClipProcessingAttribute::TONE_CURVE_SATURATION => write!(f, "tone curve saturation"),
+            ClipProcessingAttribute::TONE_CURVE_MIDPOINT => write!(f, "tone curve midpoint"),
+            ClipProcessingAttribute::TONE_CURVE_HIGHLIGHTS => write!(f, "tone curve highlights"),
+            ClipProcessingAttribute::TONE_CURVE_SHADOWS => write!(f, "tone curve shadows"),
+            ClipProcessingAttribute::TONE_CURVE_VIDEO_BLACK_LEVEL => write!(f, "tone curve video black level"),
+            ClipProcessingAttribute::TONE_CURVE_BLACK_LEVEL => write!(f, "tone curve black level"),
+            ClipProcessingAttribute::TONE_CURVE_WHITE_LEVEL => write!(f, "tone curve white level"),
+            ClipProcessingAttribute::HIGHLIGHT_RECOVERY => write!(f, "highlight recovery"),
+            ClipProcessingAttribute::ANALOG_GAIN => write!(f, "analog gain"),
+            ClipProcessingAttribute::POST_3D_LUT_MODE => write!(f, "post 3D LUT mode"),
+            ClipProcessingAttribute::EMBEDDED_POST_3D_LUT_NAME => write!(f, "embedded post 3D LUT name"),
+            ClipProcessingAttribute::EMBEDDED_POST_3D_LUT_TITLE => write!(f, "embedded post 3D LUT title"),
+            ClipProcessingAttribute::EMBEDDED_POST_3D_LUT_SIZE => write!(f, "embedded post 3D LUT size"),
+            ClipProcessingAttribute::EMBEDDED_POST_3D_LUT_DATA => write!(f, "embedded post 3D LUT data"),
+            ClipProcessingAttribute::SIDECAR_POST_3D_LUT_NAME => write!(f, "sidecar post 3D LUT name"),
+            ClipProcessingAttribute::SIDECAR_POST_3D_LUT_TITLE => write!(f, "sidecar post 3D LUT title"),
+            ClipProcessingAttribute::SIDECAR_POST_3D_LUT_SIZE => write!(f, "sidecar post 3D LUT size"),
+            ClipProcessingAttribute::SIDECAR_POST_3D_LUT_DATA => write!(f, "sidecar post 3D LUT data"),
+            ClipProcessingAttribute(other) => write!(f, "unknown clip processing attribute {:#x}", other),
+        }
+    }
+}
+
+/// Settings that control how a clip's frames are processed. Obtain a copy of a clip's settings with
+/// `Clip::clone_clip_processing_attributes`, modify it, and pass it to a job to override them.
+pub struct ClipProcessingAttributes {
+    implementation: *mut IBlackmagicRawClipProcessingAttributes,
+    validation: Option<Validation>,
+}
+
+unsafe impl Send for ClipProcessingAttributes {}
+
+impl Drop for ClipProcessingAttributes {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl ClipProcessingAttributes {
+    /// Returns the attribute's value, or `None` if it isn't set or its type can't be represented.
+    pub fn get_attribute(&mut self, attribute: ClipProcessingAttribute) -> Result<Option<Value>, Error> {
+        unsafe {
+            get_variant(|value| blackmagic_raw_clip_processing_attributes_get_clip_attribute(self.implementation, attribute.0, value), "IBlackmagicRawClipProcessingAttributes::GetClipAttribute")
+        }
+    }
+
+    /// Makes the setters reject values that the given camera type doesn't support, instead of
+    /// leaving it to the SDK to clamp them or fail. If the supported values can't be queried, for
+    /// example because the camera type is unknown, the setters return that error.
+    pub fn validate_for_camera(&mut self, constants: &Constants, camera_type: &str) {
+        self.validation = Some(Validation{
+            constants: constants.clone(),
+            camera_type: camera_type.to_string(),
+        });
+    }
+
+    /// Sets the attribute. The value must have the attribute's type, e.g. `Value::UInt16` for
+    /// `COLOR_SCIENCE_GEN`.
+    pub fn set_attribute(&mut self, attribute: ClipProcessingAttribute, value: &Value) -> Result<(), Error> {
+        let mut value = self.prepare_attribute(attribute, value)?;
+        self.set_variant(attribute, &mut value)
+    }
+
+    /// Validates a value and converts it for the SDK, without setting it.
+    fn prepare_attribute(&mut self, attribute: ClipProcessingAttribute, value: &Value) -> Result<VariantArg, Error> {
+        if let Some(ref mut validation) = self.validation {
+            let camera_type = &validation.camera_type;
+            let constants = &mut validation.constants;
+            validate(value, attribute, camera_type, constants.get_clip_processing_attribute_list(camera_type, attribute), || constants.get_clip_processing_attribute_range(camera_type, attribute), "IBlackmagicRawClipProcessingAttributes::SetClipAttribute")?;
+        }
+        VariantArg::new(value, "IBlackmagicRawClipProcessingAttributes::SetClipAttribute")
+    }
+
+    fn set_variant(&mut self, attribute: ClipProcessingAttribute, value: &mut VariantArg) -> Result<(), Error> {
+        unsafe {
+            void_result(blackmagic_raw_clip_processing_attributes_set_clip_attribute(self.implementation, attribute.0, &mut value.variant), "IBlackmagicRawClipProcessingAttributes::SetClipAttribute")
+        }
+    }
+
+    fn get_u16(&mut self, attribute: ClipProcessingAttribute) -> Result<u16, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::UInt16(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawClipProcessingAttributes::GetClipAttribute")),
+        }
+    }
+
+    fn get_f32(&mut self, attribute: ClipProcessingAttribute) -> Result<f32, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::Float(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawClipProcessingAttributes::GetClipAttribute")),
+        }
+    }
+
+    fn get_string(&mut self, attribute: ClipProcessingAttribute) -> Result<String, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::String(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawClipProcessingAttributes::GetClipAttribute")),
+        }
+    }
+
+    pub fn get_color_science_gen(&mut self) -> Result<u16, Error> {
+        self.get_u16(ClipProcessingAttribute::COLOR_SCIENCE_GEN)
+    }
+
+    pub fn set_color_science_gen(&mut self, value: u16) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::COLOR_SCIENCE_GEN, &Value::UInt16(value))
+    }
+
+    /// The gamma, such as "Blackmagic Design Film" or "Rec.709".
+    pub fn get_gamma(&mut self) -> Result<String, Error> {
+        self.get_string(ClipProcessingAttribute::GAMMA)
+    }
+
+    pub fn set_gamma(&mut self, value: &str) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::GAMMA, &Value::String(value.to_string()))
+    }
+
+    /// The gamut, such as "Blackmagic Design" or "Rec.2020".
+    pub fn get_gamut(&mut self) -> Result<String, Error> {
+        self.get_string(ClipProcessingAttribute::GAMUT)
+    }
+
+    pub fn set_gamut(&mut self, value: &str) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::GAMUT, &Value::String(value.to_string()))
+    }
+
+    /// The tone curve attributes only apply when the gamma is "Blackmagic Design Custom".
+    pub fn get_tone_curve_contrast(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_CONTRAST)
+    }
+
+    pub fn set_tone_curve_contrast(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_CONTRAST, &Value::Float(value))
+    }
+
+    pub fn get_tone_curve_saturation(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_SATURATION)
+    }
+
+    pub fn set_tone_curve_saturation(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_SATURATION, &Value::Float(value))
+    }
+
+    pub fn get_tone_curve_midpoint(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_MIDPOINT)
+    }
+
+    pub fn set_tone_curve_midpoint(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_MIDPOINT, &Value::Float(value))
+    }
+
+    pub fn get_tone_curve_highlights(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_HIGHLIGHTS)
+    }
+
+    pub fn set_tone_curve_highlights(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_HIGHLIGHTS, &Value::Float(value))
+    }
+
+    pub fn get_tone_curve_shadows(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_SHADOWS)
+    }
+
+    pub fn set_tone_curve_shadows(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_SHADOWS, &Value::Float(value))
+    }
+
+    /// Non-zero to use video black level.
+    pub fn get_tone_curve_video_black_level(&mut self) -> Result<u16, Error> {
+        self.get_u16(ClipProcessingAttribute::TONE_CURVE_VIDEO_BLACK_LEVEL)
+    }
+
+    pub fn set_tone_curve_video_black_level(&mut self, value: u16) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_VIDEO_BLACK_LEVEL, &Value::UInt16(value))
+    }
+
+    pub fn get_tone_curve_black_level(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_BLACK_LEVEL)
+    }
+
+    pub fn set_tone_curve_black_level(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_BLACK_LEVEL, &Value::Float(value))
+    }
+
+    pub fn get_tone_curve_white_level(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::TONE_CURVE_WHITE_LEVEL)
+    }
+
+    pub fn set_tone_curve_white_level(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::TONE_CURVE_WHITE_LEVEL, &Value::Float(value))
+    }
+
+    /// Non-zero to enable highlight recovery.
+    pub fn get_highlight_recovery(&mut self) -> Result<u16, Error> {
+        self.get_u16(ClipProcessingAttribute::HIGHLIGHT_RECOVERY)
+    }
+
+    pub fn set_highlight_recovery(&mut self, value: u16) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::HIGHLIGHT_RECOVERY, &Value::UInt16(value))
+    }
+
+    pub fn get_analog_gain(&mut self) -> Result<f32, Error> {
+        self.get_f32(ClipProcessingAttribute::ANALOG_GAIN)
+    }
+
+    pub fn set_analog_gain(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::ANALOG_GAIN, &Value::Float(value))
+    }
+
+    /// Selects which 3D LUT is applied after processing: "Disabled", "Embedded", or "Sidecar".
+    pub fn get_post_3d_lut_mode(&mut self) -> Result<String, Error> {
+        self.get_string(ClipProcessingAttribute::POST_3D_LUT_MODE)
+    }
+
+    pub fn set_post_3d_lut_mode(&mut self, value: &str) -> Result<(), Error> {
+        self.set_attribute(ClipProcessingAttribute::POST_3D_LUT_MODE, &Value::String(value.to_string()))
+    }
+
+    /// Reads all of the tone curve attributes at once.
+    pub fn get_tone_curve(&mut self) -> Result<ToneCurveParameters, Error> {
+        Ok(ToneCurveParameters{
+            contrast: self.get_tone_curve_contrast()?,
+            saturation: self.get_tone_curve_saturation()?,
+            midpoint: self.get_tone_curve_midpoint()?,
+            highlights: self.get_tone_curve_highlights()?,
+            shadows: self.get_tone_curve_shadows()?,
+            black_level: self.get_tone_curve_black_level()?,
+            white_level: self.get_tone_curve_white_level()?,
+            video_black_level: self.get_tone_curve_video_black_level()?,
+        })
+    }
+
+    /// Sets all of the tone curve attributes at once. Like the individual setters, they only apply
+    /// when the gamma is "Blackmagic Design Custom".
+    pub fn set_tone_curve(&mut self, parameters: &ToneCurveParameters) -> Result<(), Error> {
+        self.set_tone_curve_contrast(parameters.contrast)?;
+        self.set_tone_curve_saturation(parameters.saturation)?;
+        self.set_tone_curve_midpoint(parameters.midpoint)?;
+        self.set_tone_curve_highlights(parameters.highlights)?;
+        self.set_tone_curve_shadows(parameters.shadows)?;
+        self.set_tone_curve_black_level(parameters.black_level)?;
+        self.set_tone_curve_white_level(parameters.white_level)?;
+        self.set_tone_curve_video_black_level(parameters.video_black_level)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameProcessingAttribute(pub u32);
+
+impl FrameProcessingAttribute {
+    pub const WHITE_BALANCE_KELVIN: FrameProcessingAttribute = FrameProcessingAttribute(_BlackmagicRawFrameProcessingAttribute_blackmagicRawFrameProcessingAttributeWhiteBalanceKelvin);
+    pub const WHITE_BALANCE_TINT: FrameProcessingAttribute = FrameProcessingAttribute(_BlackmagicRawFrameProcessingAttribute_blackmagicRawFrameProcessingAttributeWhiteBalanceTint);
+    pub const EXPOSURE: FrameProcessingAttribute = FrameProcessingAttribute(_BlackmagicRawFrameProcessingAttribute_blackmagicRawFrameProcessingAttributeExposure);
+    pub const ISO: FrameProcessingAttribute = FrameProcessingAttribute(_BlackmagicRawFrameProcessingAttribute_blackmagicRawFrameProcessingAttributeISO);
+}
+
+impl fmt::Display for FrameProcessingAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameProcessingAttribute::WHITE_BALANCE_KELVIN => write!(f, "white balance kelvin"),
+            FrameProcessingAttribute::WHITE_BALANCE_TINT => write!(f, "white balance tint"),
+            FrameProcessingAttribute::EXPOSURE => write!(f, "exposure"),
+            FrameProcessingAttribute::ISO => write!(f, "ISO"),
+            FrameProcessingAttribute(other) => write!(f, "unknown frame processing attribute {:#x}", other),
+        }
+    }
+}
+
+/// Settings that control how a single frame is processed. Obtain a copy of a frame's settings with
+/// `Frame::clone_frame_processing_attributes`, modify it, and pass it to
+/// `create_job_decode_and_process_frame` to override them.
+pub struct FrameProcessingAttributes {
+    implementation: *mut IBlackmagicRawFrameProcessingAttributes,
+    validation: Option<Validation>,
+}
+
+unsafe impl Send for FrameProcessingAttributes {}
+
+impl Drop for FrameProcessingAttributes {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl FrameProcessingAttributes {
+    /// Returns the attribute's value, or `None` if it isn't set or its type can't be represented.
+    pub fn get_attribute(&mut self, attribute: FrameProcessingAttribute) -> Result<Option<Value>, Error> {
+        unsafe {
+            get_variant(|value| blackmagic_raw_frame_processing_attributes_get_frame_attribute(self.implementation, attribute.0, value), "IBlackmagicRawFrameProcessingAttributes::GetFrameAttribute")
+        }
+    }
+
+    /// Makes the setters reject values that the given camera type doesn't support, instead of
+    /// leaving it to the SDK to clamp them or fail. If the supported values can't be queried, for
+    /// example because the camera type is unknown, the setters return that error.
+    pub fn validate_for_camera(&mut self, constants: &Constants, camera_type: &str) {
+        self.validation = Some(Validation{
+            constants: constants.clone(),
+            camera_type: camera_type.to_string(),
+        });
+    }
+
+    /// Sets the attribute. The value must have the attribute's type, e.g. `Value::UInt32` for
+    /// `WHITE_BALANCE_KELVIN`.
+    pub fn set_attribute(&mut self, attribute: FrameProcessingAttribute, value: &Value) -> Result<(), Error> {
+        if let Some(ref mut validation) = self.validation {
+            let camera_type = &validation.camera_type;
+            let constants = &mut validation.constants;
+            validate(value, attribute, camera_type, constants.get_frame_processing_attribute_list(camera_type, attribute), || constants.get_frame_processing_attribute_range(camera_type, attribute), "IBlackmagicRawFrameProcessingAttributes::SetFrameAttribute")?;
+        }
+        let mut value = VariantArg::new(value, "IBlackmagicRawFrameProcessingAttributes::SetFrameAttribute")?;
+        unsafe {
+            void_result(blackmagic_raw_frame_processing_attributes_set_frame_attribute(self.implementation, attribute.0, &mut value.variant), "IBlackmagicRawFrameProcessingAttributes::SetFrameAttribute")
+        }
+    }
+
+    fn get_i16(&mut self, attribute: FrameProcessingAttribute) -> Result<i16, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::Int16(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawFrameProcessingAttributes::GetFrameAttribute")),
+        }
+    }
+
+    fn get_u16(&mut self, attribute: FrameProcessingAttribute) -> Result<u16, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::UInt16(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawFrameProcessingAttributes::GetFrameAttribute")),
+        }
+    }
+
+    fn get_u32(&mut self, attribute: FrameProcessingAttribute) -> Result<u32, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::UInt32(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawFrameProcessingAttributes::GetFrameAttribute")),
+        }
+    }
+
+    fn get_f32(&mut self, attribute: FrameProcessingAttribute) -> Result<f32, Error> {
+        match self.get_attribute(attribute)? {
+            Some(Value::Float(v)) => Ok(v),
+            _ => Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawFrameProcessingAttributes::GetFrameAttribute")),
+        }
+    }
+
+    pub fn get_white_balance_kelvin(&mut self) -> Result<u32, Error> {
+        self.get_u32(FrameProcessingAttribute::WHITE_BALANCE_KELVIN)
+    }
+
+    pub fn set_white_balance_kelvin(&mut self, value: u32) -> Result<(), Error> {
+        self.set_attribute(FrameProcessingAttribute::WHITE_BALANCE_KELVIN, &Value::UInt32(value))
+    }
+
+    pub fn get_white_balance_tint(&mut self) -> Result<i16, Error> {
+        self.get_i16(FrameProcessingAttribute::WHITE_BALANCE_TINT)
+    }
+
+    pub fn set_white_balance_tint(&mut self, value: i16) -> Result<(), Error> {
+        self.set_attribute(FrameProcessingAttribute::WHITE_BALANCE_TINT, &Value::Int16(value))
+    }
+
+    /// The exposure adjustment, in stops.
+    pub fn get_exposure(&mut self) -> Result<f32, Error> {
+        self.get_f32(FrameProcessingAttribute::EXPOSURE)
+    }
+
+    pub fn set_exposure(&mut self, value: f32) -> Result<(), Error> {
+        self.set_attribute(FrameProcessingAttribute::EXPOSURE, &Value::Float(value))
+    }
+
+    pub fn get_iso(&mut self) -> Result<u16, Error> {
+        self.get_u16(FrameProcessingAttribute::ISO)
+    }
+
+    pub fn set_iso(&mut self, value: u16) -> Result<(), Error> {
+        self.set_attribute(FrameProcessingAttribute::ISO, &Value::UInt16(value))
+    }
+}
+
+/// The parameters that define a tone curve. These correspond to the `TONE_CURVE_*` clip processing
+/// attributes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneCurveParameters {
+    pub contrast: f32,
+    pub saturation: f32,
+    pub midpoint: f32,
+    pub highlights: f32,
+    pub shadows: f32,
+    pub black_level: f32,
+    pub white_level: f32,
+    /// Non-zero to use video black level.
+    pub video_black_level: u16,
+}
+
+/// Looks up and evaluates tone curves. Obtain it with `Codec::get_tone_curve`.
+pub struct ToneCurve {
+    implementation: *mut IBlackmagicRawToneCurve,
+}
+
+unsafe impl Send for ToneCurve {}
+
+impl Drop for ToneCurve {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl ToneCurve {
+    /// Returns the default tone curve for a camera type, gamma, and color science generation. The
+    /// SDK only provides these for the Blackmagic Design gamut with the "Blackmagic Design Film",
+    /// "Blackmagic Design Extended Video", or "Blackmagic Design Custom" gammas.
+    pub fn get_tone_curve(&mut self, camera_type: &str, gamma: &str, color_science_gen: u16) -> Result<ToneCurveParameters, Error> {
+        let operation = "IBlackmagicRawToneCurve::GetToneCurve";
+        let c_camera_type = c_string(camera_type, operation)?;
+        let c_gamma = c_string(gamma, operation)?;
+        let mut ret = ToneCurveParameters{
+            contrast: 0.0,
+            saturation: 0.0,
+            midpoint: 0.0,
+            highlights: 0.0,
+            shadows: 0.0,
+            black_level: 0.0,
+            white_level: 0.0,
+            video_black_level: 0,
+        };
+        unsafe {
+            void_result(blackmagic_raw_tone_curve_get_tone_curve(self.implementation, c_camera_type.as_ptr(), c_gamma.as_ptr(), color_science_gen, &mut ret.contrast, &mut ret.saturation, &mut ret.midpoint, &mut ret.highlights, &mut ret.shadows, &mut ret.black_level, &mut ret.white_level, &mut ret.video_black_level), operation)?;
+        }
+        return Ok(ret)
+    }
+
+    /// Evaluates a tone curve at `sample_count` evenly spaced points, which is suitable for
+    /// drawing it.
+    pub fn evaluate_tone_curve(&mut self, camera_type: &str, color_science_gen: u16, parameters: &ToneCurveParameters, sample_count: usize) -> Result<Vec<f32>, Error> {
+        let operation = "IBlackmagicRawToneCurve::EvaluateToneCurve";
+        let c_camera_type = c_string(camera_type, operation)?;
+        if sample_count > u32::MAX as usize {
+            return Err(Error::new(ErrorCode::InvalidArg, operation));
+        }
+        let mut ret = vec![0.0; sample_count];
+        if sample_count > 0 {
+            unsafe {
+                void_result(blackmagic_raw_tone_curve_evaluate_tone_curve(self.implementation, c_camera_type.as_ptr(), color_science_gen, parameters.contrast, parameters.saturation, parameters.midpoint, parameters.highlights, parameters.shadows, parameters.black_level, parameters.white_level, parameters.video_black_level, ret.as_mut_ptr(), sample_count as u32), operation)?;
+            }
+        }
+        return Ok(ret)
+    }
+}
+
+/// Per-camera ranges and lists of supported processing attribute values. Obtain it with
+/// `Codec::get_constants` and look up a clip's camera type with `Clip::get_camera_type`.
+pub struct Constants {
+    implementation: *mut IBlackmagicRawConstants,
+}
+
+unsafe impl Send for Constants {}
+
+impl Drop for Constants {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl Clone for Constants {
+    fn clone(&self) -> Constants {
+        unsafe {
+            blackmagic_raw_unknown_add_ref(self.implementation as *mut IUnknown);
+        }
+        Constants{
+            implementation: self.implementation,
+        }
+    }
+}
+
+impl Constants {
+    /// Returns the minimum and maximum values of a numeric attribute.
+    pub fn get_clip_processing_attribute_range(&mut self, camera_type: &str, attribute: ClipProcessingAttribute) -> Result<(Value, Value), Error> {
+        let operation = "IBlackmagicRawConstants::GetClipProcessingAttributeRange";
+        let c_camera_type = c_string(camera_type, operation)?;
+        unsafe {
+            get_range(|min, max| blackmagic_raw_constants_get_clip_processing_attribute_range(self.implementation, c_camera_type.as_ptr(), attribute.0, min, max), operation)
+        }
+    }
+
+    /// Returns the values an attribute may take, such as the supported gammas. The list is empty
+    /// for attributes that are limited by a range instead.
+    pub fn get_clip_processing_attribute_list(&mut self, camera_type: &str, attribute: ClipProcessingAttribute) -> Result<Vec<Value>, Error> {
+        let operation = "IBlackmagicRawConstants::GetClipProcessingAttributeList";
+        let c_camera_type = c_string(camera_type, operation)?;
         unsafe {
-            void_result(blackmagic_raw_processed_image_get_width(self.implementation, &mut out))?
+            get_list(|array, count| blackmagic_raw_constants_get_clip_processing_attribute_list(self.implementation, c_camera_type.as_ptr(), attribute.0, array, count), operation)
         }
-        Ok(out)
     }
 
-    pub fn get_height(&mut self) -> Result<u32, Error> {
-        let mut out = 0;
+    /// Returns the minimum and maximum values of a numeric attribute.
+    pub fn get_frame_processing_attribute_range(&mut self, camera_type: &str, attribute: FrameProcessingAttribute) -> Result<(Value, Value), Error> {
+        let operation = "IBlackmagicRawConstants::GetFrameProcessingAttributeRange";
+        let c_camera_type = c_string(camera_type, operation)?;
         unsafe {
-            void_result(blackmagic_raw_processed_image_get_height(self.implementation, &mut out))?
+            get_range(|min, max| blackmagic_raw_constants_get_frame_processing_attribute_range(self.implementation, c_camera_type.as_ptr(), attribute.0, min, max), operation)
         }
-        Ok(out)
     }
 
-    pub fn get_resource_size_bytes(&mut self) -> Result<u32, Error> {
-        let mut out = 0;
+    /// Returns the values an attribute may take, such as the supported ISOs. The list is empty for
+    /// attributes that are limited by a range instead.
+    pub fn get_frame_processing_attribute_list(&mut self, camera_type: &str, attribute: FrameProcessingAttribute) -> Result<Vec<Value>, Error> {
+        let operation = "IBlackmagicRawConstants::GetFrameProcessingAttributeList";
+        let c_camera_type = c_string(camera_type, operation)?;
         unsafe {
-            void_result(blackmagic_raw_processed_image_get_resource_size_bytes(self.implementation, &mut out))?
+            get_list(|array, count| blackmagic_raw_constants_get_frame_processing_attribute_list(self.implementation, c_camera_type.as_ptr(), attribute.0, array, count), operation)
         }
-        Ok(out)
     }
 
-    pub fn get_resource(&mut self) -> Result<&[u8], Error> {
-        let len = self.get_resource_size_bytes()?;
+    /// Returns the ISOs that are available at the given analog gain.
+    pub fn get_iso_list_for_analog_gain(&mut self, camera_type: &str, analog_gain: f32) -> Result<Vec<u16>, Error> {
+        let operation = "IBlackmagicRawConstants::GetISOListForAnalogGain";
+        let c_camera_type = c_string(camera_type, operation)?;
+        let mut count = 0;
         unsafe {
-            let mut buf: *mut c_void = std::ptr::null_mut();
-            void_result(blackmagic_raw_processed_image_get_resource(self.implementation, &mut buf))?;
-            Ok(std::slice::from_raw_parts(buf as *mut u8, len as usize))
+            void_result(blackmagic_raw_constants_get_iso_list_for_analog_gain(self.implementation, c_camera_type.as_ptr(), analog_gain, std::ptr::null_mut(), &mut count), operation)?;
+            let mut ret = vec![0; count as usize];
+            if count > 0 {
+                void_result(blackmagic_raw_constants_get_iso_list_for_analog_gain(self.implementation, c_camera_type.as_ptr(), analog_gain, ret.as_mut_ptr(), &mut count), operation)?;
+            }
+            ret.truncate(count as usize);
+            Ok(ret)
         }
     }
 }
 
-pub struct ClipProcessingAttributes {
-    implementation: *mut IBlackmagicRawClipProcessingAttributes,
+unsafe fn get_variant<F: FnOnce(*mut Variant) -> HRESULT>(f: F, operation: &'static str) -> Result<Option<Value>, Error> {
+    let mut value = Variant::empty();
+    VariantInit(&mut value);
+    // S_FALSE or an empty variant means there's no value.
+    let result = match void_option_result(f(&mut value), operation) {
+        Ok(Some(())) if value.vt != _BlackmagicRawVariantType_blackmagicRawVariantTypeEmpty => Value::new_from_variant(&mut value),
+        Ok(_) => Ok(None),
+        Err(e) => Err(e),
+    };
+    VariantClear(&mut value);
+    result
 }
 
-unsafe impl Send for ClipProcessingAttributes {}
+unsafe fn get_range<F: FnOnce(*mut Variant, *mut Variant) -> HRESULT>(f: F, operation: &'static str) -> Result<(Value, Value), Error> {
+    let mut min = Variant::empty();
+    let mut max = Variant::empty();
+    VariantInit(&mut min);
+    VariantInit(&mut max);
+    let result = void_result(f(&mut min, &mut max), operation).and_then(|_| {
+        match (Value::new_from_variant(&mut min)?, Value::new_from_variant(&mut max)?) {
+            (Some(min), Some(max)) => Ok((min, max)),
+            _ => Err(Error::new(ErrorCode::Unexpected, operation)),
+        }
+    });
+    VariantClear(&mut min);
+    VariantClear(&mut max);
+    result
+}
 
-impl Drop for ClipProcessingAttributes {
-    fn drop(&mut self) {
-        unsafe {
-            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+unsafe fn get_list<F: FnMut(*mut Variant, *mut u32) -> HRESULT>(mut f: F, operation: &'static str) -> Result<Vec<Value>, Error> {
+    let mut count = 0;
+    void_result(f(std::ptr::null_mut(), &mut count), operation)?;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let mut variants: Vec<Variant> = (0..count).map(|_| Variant::empty()).collect();
+    for v in variants.iter_mut() {
+        VariantInit(v);
+    }
+    let result = void_result(f(variants.as_mut_ptr(), &mut count), operation).and_then(|_| {
+        let mut values = Vec::new();
+        for v in variants.iter_mut().take(count as usize) {
+            if let Some(value) = Value::new_from_variant(v)? {
+                values.push(value);
+            }
         }
+        Ok(values)
+    });
+    for v in variants.iter_mut() {
+        VariantClear(v);
     }
+    result
 }
 
-pub struct FrameProcessingAttributes {
-    implementation: *mut IBlackmagicRawFrameProcessingAttributes,
+/// The camera that attribute setters validate values for.
+struct Validation {
+    constants: Constants,
+    camera_type: String,
 }
 
-unsafe impl Send for FrameProcessingAttributes {}
-
-impl Drop for FrameProcessingAttributes {
-    fn drop(&mut self) {
-        unsafe {
-            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+/// Checks `value` against the camera's list of supported values or, if it doesn't have one, its
+/// range. Attributes that have neither aren't checked.
+/// Checks a value against the attribute's list of allowed values if it has one, and otherwise
+/// against its range. Errors from the queries are returned, except that `ErrorCode::NotImpl` from
+/// the range query means the attribute has no range.
+fn validate<A: fmt::Display, R: FnOnce() -> Result<(Value, Value), Error>>(value: &Value, attribute: A, camera_type: &str, list: Result<Vec<Value>, Error>, range: R, operation: &'static str) -> Result<(), Error> {
+    match list? {
+        ref list if !list.is_empty() => {
+            if list.iter().any(|v| v.approx_eq(value)) {
+                return Ok(());
+            }
+            let allowed: Vec<String> = list.iter().map(|v| v.describe()).collect();
+            return Err(Error::InvalidValue{
+                operation: operation,
+                message: format!("{} {} isn't supported by {} cameras, expected one of {}", attribute, value.describe(), camera_type, allowed.join(", ")),
+            });
+        },
+        _ => {},
+    }
+    let v = match value.to_f64() {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let (min, max) = match range() {
+        Ok(range) => range,
+        Err(ref e) if e.code() == Some(ErrorCode::NotImpl) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if let (Some(lo), Some(hi)) = (min.to_f64(), max.to_f64()) {
+        if v < lo || v > hi {
+            return Err(Error::InvalidValue{
+                operation: operation,
+                message: format!("{} {} is outside the range supported by {} cameras, {} to {}", attribute, value.describe(), camera_type, min.describe(), max.describe()),
+            });
         }
     }
+    Ok(())
 }
 
 pub trait Callback {
@@ -489,53 +2699,134 @@ pub trait Callback {
     fn prepare_pipeline_complete(&mut self, _result: Result<(), Error>) {}
 }
 
+/// The state owned by the SDK callback object. Panics raised by the callback are stored in `panic`
+/// so that the codec can resume them on the thread that is waiting for the jobs.
+struct CallbackContext {
+    callback: Arc<Mutex<dyn Callback + Send>>,
+    panic: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
+}
+
+impl CallbackContext {
+    fn callback(&self) -> MutexGuard<'_, dyn Callback + Send + 'static> {
+        self.callback.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Registered when the caller hasn't set a callback of their own.
+struct NoCallback;
+
+impl Callback for NoCallback {}
+
+thread_local! {
+    // Set while a callback or job handler is running, so that blocking calls that would wait on the
+    // SDK's threads can fail instead of deadlocking.
+    static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+fn in_callback() -> bool {
+    IN_CALLBACK.with(|c| c.get())
+}
+
+/// Runs `f` unless an earlier callback panicked. `completed_job` is the job that this callback
+/// completes, if any, so that its handler can be released when `f` isn't run.
+unsafe fn dispatch<F: FnOnce(&CallbackContext)>(context: *mut CallbackContext, completed_job: *mut IBlackmagicRawJob, f: F) {
+    let context = &*context;
+    let mut panic = context.panic.lock().unwrap_or_else(|e| e.into_inner());
+    if panic.is_some() {
+        drop(panic);
+        if !completed_job.is_null() {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(take_job_handler(completed_job))));
+        }
+        return;
+    }
+    drop(panic);
+    // Any references handed to the callback are created inside the closure so that they're released
+    // while unwinding.
+    let was_in_callback = IN_CALLBACK.with(|c| c.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(context)));
+    IN_CALLBACK.with(|c| c.set(was_in_callback));
+    if let Err(payload) = result {
+        panic = context.panic.lock().unwrap_or_else(|e| e.into_inner());
+        if panic.is_none() {
+            *panic = Some(payload);
+        }
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn callback_drop(context: *mut CallbackContext) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(context))));
+}
+
 #[no_mangle]
-unsafe extern "C" fn callback_read_complete(implementation: *mut Box<dyn Callback>, job: *mut IBlackmagicRawJob, result: HRESULT, frame: *mut IBlackmagicRawFrame) {
-    let implementation = &mut *implementation;
-    implementation.read_complete(Job::new_ref(job), void_result(result).map(|_| Frame::new_ref(frame)));
+unsafe extern "C" fn callback_read_complete(context: *mut CallbackContext, job: *mut IBlackmagicRawJob, result: HRESULT, frame: *mut IBlackmagicRawFrame) {
+    dispatch(context, job, |context| {
+        let handler = take_job_handler(job);
+        let job = Job::new_ref(job);
+        let result = void_result(result, "IBlackmagicRawCallback::ReadComplete").map(|_| Frame::new_ref(frame));
+        match handler.map(|h| *h) {
+            Some(JobHandler::ReadFrame(handler)) => handler(job, result),
+            _ => context.callback().read_complete(job, result),
+        }
+    });
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_decode_complete(implementation: *mut Box<dyn Callback>, job: *mut IBlackmagicRawJob, result: HRESULT) {
-    let implementation = &mut *implementation;
-    implementation.decode_complete(Job::new_ref(job), void_result(result));
+unsafe extern "C" fn callback_decode_complete(context: *mut CallbackContext, job: *mut IBlackmagicRawJob, result: HRESULT) {
+    dispatch(context, std::ptr::null_mut(), |context| context.callback().decode_complete(Job::new_ref(job), void_result(result, "IBlackmagicRawCallback::DecodeComplete")));
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_process_complete(implementation: *mut Box<dyn Callback>, job: *mut IBlackmagicRawJob, result: HRESULT, processed_image: *mut IBlackmagicRawProcessedImage) {
-    let implementation = &mut *implementation;
-    implementation.process_complete(Job::new_ref(job), void_result(result).map(|_| ProcessedImage::new_ref(processed_image)));
+unsafe extern "C" fn callback_process_complete(context: *mut CallbackContext, job: *mut IBlackmagicRawJob, result: HRESULT, processed_image: *mut IBlackmagicRawProcessedImage) {
+    dispatch(context, job, |context| {
+        let handler = take_job_handler(job);
+        let job = Job::new_ref(job);
+        let result = void_result(result, "IBlackmagicRawCallback::ProcessComplete").map(|_| ProcessedImage::new_ref(processed_image));
+        match handler.map(|h| *h) {
+            Some(JobHandler::DecodeAndProcessFrame(handler)) => handler(job, result),
+            _ => context.callback().process_complete(job, result),
+        }
+    });
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_trim_progress(implementation: *mut Box<dyn Callback>, job: *mut IBlackmagicRawJob, progress: c_float) {
-    let implementation = &mut *implementation;
-    implementation.trim_progress(Job::new_ref(job), progress as _);
+unsafe extern "C" fn callback_trim_progress(context: *mut CallbackContext, job: *mut IBlackmagicRawJob, progress: c_float) {
+    dispatch(context, std::ptr::null_mut(), |context| {
+        let handler = job_handler(job);
+        match handler.as_mut() {
+            Some(&mut JobHandler::Trim{progress: ref mut handler, ..}) => handler(Job::new_ref(job), progress as _),
+            _ => context.callback().trim_progress(Job::new_ref(job), progress as _),
+        }
+    });
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_trim_complete(implementation: *mut Box<dyn Callback>, job: *mut IBlackmagicRawJob, result: HRESULT) {
-    let implementation = &mut *implementation;
-    implementation.trim_complete(Job::new_ref(job), void_result(result));
+unsafe extern "C" fn callback_trim_complete(context: *mut CallbackContext, job: *mut IBlackmagicRawJob, result: HRESULT) {
+    dispatch(context, job, |context| {
+        let handler = take_job_handler(job);
+        let job = Job::new_ref(job);
+        let result = void_result(result, "IBlackmagicRawCallback::TrimComplete");
+        match handler.map(|h| *h) {
+            Some(JobHandler::Trim{complete, ..}) => complete(job, result),
+            _ => context.callback().trim_complete(job, result),
+        }
+    });
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_sidecar_metadata_parse_warning(implementation: *mut Box<dyn Callback>, clip: *mut IBlackmagicRawClip, filename: *const c_char, line_number: u32, info: *const c_char) {
-    let implementation = &mut *implementation;
-    implementation.sidecar_metadata_parse_warning(Clip::new_ref(clip), CStr::from_ptr(filename).to_str().unwrap_or(""), line_number, CStr::from_ptr(info).to_str().unwrap_or(""));
+unsafe extern "C" fn callback_sidecar_metadata_parse_warning(context: *mut CallbackContext, clip: *mut IBlackmagicRawClip, filename: *const c_char, line_number: u32, info: *const c_char) {
+    dispatch(context, std::ptr::null_mut(), |context| context.callback().sidecar_metadata_parse_warning(Clip::new_ref(clip), CStr::from_ptr(filename).to_str().unwrap_or(""), line_number, CStr::from_ptr(info).to_str().unwrap_or("")));
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_sidecar_metadata_parse_error(implementation: *mut Box<dyn Callback>, clip: *mut IBlackmagicRawClip, filename: *const c_char, line_number: u32, info: *const c_char) {
-    let implementation = &mut *implementation;
-    implementation.sidecar_metadata_parse_error(Clip::new_ref(clip), CStr::from_ptr(filename).to_str().unwrap_or(""), line_number, CStr::from_ptr(info).to_str().unwrap_or(""));
+unsafe extern "C" fn callback_sidecar_metadata_parse_error(context: *mut CallbackContext, clip: *mut IBlackmagicRawClip, filename: *const c_char, line_number: u32, info: *const c_char) {
+    dispatch(context, std::ptr::null_mut(), |context| context.callback().sidecar_metadata_parse_error(Clip::new_ref(clip), CStr::from_ptr(filename).to_str().unwrap_or(""), line_number, CStr::from_ptr(info).to_str().unwrap_or("")));
 }
 
 #[no_mangle]
-unsafe extern "C" fn callback_prepare_pipeline_complete(implementation: *mut Box<dyn Callback>, _user_data: *mut c_void, result: HRESULT) {
-    let implementation = &mut *implementation;
+unsafe extern "C" fn callback_prepare_pipeline_complete(context: *mut CallbackContext, _user_data: *mut c_void, result: HRESULT) {
     // TODO: pass along user data?
-    implementation.prepare_pipeline_complete(void_result(result));
+    dispatch(context, std::ptr::null_mut(), |context| context.callback().prepare_pipeline_complete(void_result(result, "IBlackmagicRawCallback::PreparePipelineComplete")));
 }
 
 pub struct MetadataIterator {
@@ -552,6 +2843,8 @@ impl Drop for MetadataIterator {
     }
 }
 
+/// A metadata or processing attribute value.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     UInt8(u8),
     Int16(i16),
@@ -563,21 +2856,288 @@ pub enum Value {
     Array(Vec<Value>),
 }
 
+impl Variant {
+    fn empty() -> Variant {
+        Variant{
+            vt: _BlackmagicRawVariantType_blackmagicRawVariantTypeEmpty,
+            __bindgen_anon_1: Variant__bindgen_ty_1{
+                iVal: 0,
+            },
+        }
+    }
+}
+
+/// A variant built from a `Value` to pass into the SDK. It releases anything it allocated when
+/// dropped.
+struct VariantArg {
+    variant: Variant,
+    // On Linux, string variants point into this.
+    _string: Option<CString>,
+}
+
+impl VariantArg {
+    fn new(value: &Value, operation: &'static str) -> Result<VariantArg, Error> {
+        let mut ret = VariantArg{
+            variant: Variant::empty(),
+            _string: None,
+        };
+        match *value {
+            Value::UInt8(v) => {
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeU8;
+                ret.variant.__bindgen_anon_1.uiVal = v as u16;
+            },
+            Value::Int16(v) => {
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeS16;
+                ret.variant.__bindgen_anon_1.iVal = v;
+            },
+            Value::UInt16(v) => {
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeU16;
+                ret.variant.__bindgen_anon_1.uiVal = v;
+            },
+            Value::Int32(v) => {
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeS32;
+                ret.variant.__bindgen_anon_1.intVal = v;
+            },
+            Value::UInt32(v) => {
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeU32;
+                ret.variant.__bindgen_anon_1.uintVal = v;
+            },
+            Value::Float(v) => {
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeFloat32;
+                ret.variant.__bindgen_anon_1.fltVal = v;
+            },
+            Value::String(ref v) => {
+                let s = c_string(v, operation)?;
+                unsafe {
+                    blackmagic_raw_variant_set_string(&mut ret.variant, s.as_ptr());
+                }
+                ret._string = Some(s);
+            },
+            Value::Array(ref values) => {
+                let arr = unsafe {
+                    match values.first() {
+                        Some(&Value::UInt8(_)) => new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeU8, &array_elements(values, |v| match *v { Value::UInt8(v) => Some(v), _ => None }, operation)?, operation)?,
+                        Some(&Value::Int16(_)) => new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeS16, &array_elements(values, |v| match *v { Value::Int16(v) => Some(v), _ => None }, operation)?, operation)?,
+                        Some(&Value::UInt16(_)) => new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeU16, &array_elements(values, |v| match *v { Value::UInt16(v) => Some(v), _ => None }, operation)?, operation)?,
+                        Some(&Value::Int32(_)) => new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeS32, &array_elements(values, |v| match *v { Value::Int32(v) => Some(v), _ => None }, operation)?, operation)?,
+                        Some(&Value::UInt32(_)) => new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeU32, &array_elements(values, |v| match *v { Value::UInt32(v) => Some(v), _ => None }, operation)?, operation)?,
+                        Some(&Value::Float(_)) => new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeFloat32, &array_elements(values, |v| match *v { Value::Float(v) => Some(v), _ => None }, operation)?, operation)?,
+                        // The SDK doesn't define arrays of strings or nested arrays, and an empty
+                        // array has no element type.
+                        Some(&Value::String(_)) => return Err(Error::InvalidValue{
+                            operation: operation,
+                            message: "arrays of strings aren't supported".to_string(),
+                        }),
+                        Some(&Value::Array(_)) => return Err(Error::InvalidValue{
+                            operation: operation,
+                            message: "nested arrays aren't supported".to_string(),
+                        }),
+                        None => return Err(Error::InvalidValue{
+                            operation: operation,
+                            message: "empty arrays aren't supported".to_string(),
+                        }),
+                    }
+                };
+                ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeSafeArray;
+                ret.variant.__bindgen_anon_1.parray = arr;
+            },
+        }
+        Ok(ret)
+    }
+
+    /// Creates a `Float` array directly from a slice, which avoids a `Value` per element for large
+    /// arrays such as 3D LUT data.
+    fn new_f32_array(values: &[f32], operation: &'static str) -> Result<VariantArg, Error> {
+        let mut ret = VariantArg{
+            variant: Variant::empty(),
+            _string: None,
+        };
+        unsafe {
+            ret.variant.__bindgen_anon_1.parray = new_safe_array(_BlackmagicRawVariantType_blackmagicRawVariantTypeFloat32, values, operation)?;
+        }
+        ret.variant.vt = _BlackmagicRawVariantType_blackmagicRawVariantTypeSafeArray;
+        Ok(ret)
+    }
+}
+
+/// Extracts the elements of an array value, which must all have the same type.
+fn array_elements<T, F: Fn(&Value) -> Option<T>>(values: &[Value], f: F, operation: &'static str) -> Result<Vec<T>, Error> {
+    values.iter().map(|v| f(v).ok_or_else(|| Error::InvalidValue{
+        operation: operation,
+        message: format!("array elements must all have the same type, found {}", v.describe()),
+    })).collect()
+}
+
+/// Creates a one-dimensional SafeArray holding a copy of `values`. The caller must destroy it.
+unsafe fn new_safe_array<T: Copy>(variant_type: BlackmagicRawVariantType, values: &[T], operation: &'static str) -> Result<*mut SafeArray, Error> {
+    let mut bound = SafeArrayBound{
+        lLbound: 0,
+        cElements: values.len() as u32,
+    };
+    let arr = SafeArrayCreate(variant_type, 1, &mut bound);
+    if arr.is_null() {
+        return Err(Error::new(ErrorCode::OutOfMemory, operation));
+    }
+    let mut data: *mut c_void = std::ptr::null_mut();
+    if let Err(e) = void_result(SafeArrayAccessData(arr, &mut data), "SafeArrayAccessData") {
+        SafeArrayDestroy(arr);
+        return Err(e);
+    }
+    std::ptr::copy_nonoverlapping(values.as_ptr(), data as *mut T, values.len());
+    SafeArrayUnaccessData(arr);
+    return Ok(arr)
+}
+
+impl Drop for VariantArg {
+    fn drop(&mut self) {
+        if self.variant.vt == _BlackmagicRawVariantType_blackmagicRawVariantTypeString {
+            unsafe {
+                blackmagic_raw_variant_release_string(&mut self.variant);
+            }
+        }
+        if self.variant.vt == _BlackmagicRawVariantType_blackmagicRawVariantTypeSafeArray {
+            unsafe {
+                SafeArrayDestroy(self.variant.__bindgen_anon_1.parray);
+            }
+        }
+    }
+}
+
 impl Value {
+    fn to_f64(&self) -> Option<f64> {
+        match *self {
+            Value::UInt8(v) => Some(v as f64),
+            Value::Int16(v) => Some(v as f64),
+            Value::UInt16(v) => Some(v as f64),
+            Value::Int32(v) => Some(v as f64),
+            Value::UInt32(v) => Some(v as f64),
+            Value::Float(v) => Some(v as f64),
+            Value::String(_) | Value::Array(_) => None,
+        }
+    }
+
+    /// Compares values loosely enough that a float read back from the SDK matches the one that was
+    /// written.
+    fn approx_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => a == b,
+            _ => match (self.to_f64(), other.to_f64()) {
+                (Some(a), Some(b)) => (a - b).abs() <= 1e-4 * a.abs().max(1.0),
+                _ => false,
+            },
+        }
+    }
+
+    /// Formats the value for error messages, quoting strings so that they stand out.
+    fn describe(&self) -> String {
+        match *self {
+            Value::String(ref v) => format!("{:?}", v),
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn as_u8(&self) -> Option<u8> {
+        match *self {
+            Value::UInt8(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        match *self {
+            Value::Int16(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_u16(&self) -> Option<u16> {
+        match *self {
+            Value::UInt16(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Value::Int32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self {
+            Value::UInt32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Value::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of an array of `UInt32` values, such as the "sensor_rate" metadata.
+    /// Arrays store a `Value` per element, so there's no `u32` slice to borrow and the elements are
+    /// copied instead.
+    pub fn to_u32_vec(&self) -> Option<Vec<u32>> {
+        self.as_array()?.iter().map(|v| v.as_u32()).collect()
+    }
+
+    /// Returns the elements of an array of `Float` values, such as 3D LUT data. As with
+    /// `to_u32_vec`, the elements are copied.
+    pub fn to_f32_vec(&self) -> Option<Vec<f32>> {
+        self.as_array()?.iter().map(|v| v.as_f32()).collect()
+    }
+
+    /// Converts a variant filled in by the SDK. Returns `None` for types that have no equivalent.
+    unsafe fn new_from_variant(value: &mut Variant) -> Result<Option<Value>, Error> {
+        Ok(Some(match value.vt {
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeU8 => Value::UInt8(value.__bindgen_anon_1.uiVal as u8),
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeS16 => Value::Int16(value.__bindgen_anon_1.iVal),
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeU16 => Value::UInt16(value.__bindgen_anon_1.uiVal),
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeS32 => Value::Int32(value.__bindgen_anon_1.intVal),
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeU32 => Value::UInt32(value.__bindgen_anon_1.uintVal),
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeFloat32 => Value::Float(value.__bindgen_anon_1.fltVal),
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeString => {
+                let mut buf: *mut Buffer = std::ptr::null_mut();
+                blackmagic_raw_variant_get_string(value, &mut buf);
+                Value::String(buffer_to_string(buf))
+            },
+            _BlackmagicRawVariantType_blackmagicRawVariantTypeSafeArray => return Value::new_from_safe_array(value.__bindgen_anon_1.parray),
+            _ => return Ok(None),
+        }))
+    }
+
     unsafe fn new_from_safe_array(arr: *mut SafeArray) -> Result<Option<Value>, Error> {
         let mut t = _BlackmagicRawVariantType_blackmagicRawVariantTypeEmpty;
-        void_result(SafeArrayGetVartype(arr, &mut t))?;
+        void_result(SafeArrayGetVartype(arr, &mut t), "SafeArrayGetVartype")?;
 
         let mut u = 0;
-        void_result(SafeArrayGetUBound(arr, 1, &mut u))?;
+        void_result(SafeArrayGetUBound(arr, 1, &mut u), "SafeArrayGetUBound")?;
 
         let mut l = 0;
-        void_result(SafeArrayGetLBound(arr, 1, &mut l))?;
+        void_result(SafeArrayGetLBound(arr, 1, &mut l), "SafeArrayGetLBound")?;
 
 		let len = (u - l) + 1;
 
         let mut data: *mut c_void = std::ptr::null_mut();
-        void_result(SafeArrayAccessData(arr, &mut data))?;
+        void_result(SafeArrayAccessData(arr, &mut data), "SafeArrayAccessData")?;
 
         let ret = match t {
             _BlackmagicRawVariantType_blackmagicRawVariantTypeU8 => {
@@ -607,32 +3167,166 @@ impl Value {
             _ => None,
         };
 
-        void_result(SafeArrayUnaccessData(arr))?;
+        void_result(SafeArrayUnaccessData(arr), "SafeArrayUnaccessData")?;
 
         return Ok(ret)
     }
 }
 
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::UInt8(v) => v.fmt(f),
+            Value::Int16(v) => v.fmt(f),
+            Value::UInt16(v) => v.fmt(f),
+            Value::Int32(v) => v.fmt(f),
+            Value::UInt32(v) => v.fmt(f),
+            Value::Float(v) => v.fmt(f),
+            Value::String(ref v) => v.fmt(f),
+            Value::Array(ref v) => write!(f, "[{}]", v.iter().map(|v| v.describe()).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+impl From<u8> for Value {
+    fn from(v: u8) -> Value {
+        Value::UInt8(v)
+    }
+}
+
+impl From<i16> for Value {
+    fn from(v: i16) -> Value {
+        Value::Int16(v)
+    }
+}
+
+impl From<u16> for Value {
+    fn from(v: u16) -> Value {
+        Value::UInt16(v)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Value {
+        Value::Int32(v)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(v: u32) -> Value {
+        Value::UInt32(v)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Value {
+        Value::Float(v)
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Value {
+        Value::String(v)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(v: &'a str) -> Value {
+        Value::String(v.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Value {
+        Value::Array(v.into_iter().map(|v| v.into()).collect())
+    }
+}
+
+fn value_type_error(expected: &str, value: &Value) -> Error {
+    Error::InvalidValue{
+        operation: "Value::try_into",
+        message: format!("expected {}, found {}", expected, value.describe()),
+    }
+}
+
+/// Converts integer values to any integer type that can hold them exactly.
+macro_rules! value_try_into_integer {
+    ($t:ty) => {
+        impl TryFrom<Value> for $t {
+            type Error = Error;
+
+            fn try_from(value: Value) -> Result<$t, Error> {
+                let converted = match value {
+                    Value::UInt8(v) => <$t>::try_from(v).ok(),
+                    Value::Int16(v) => <$t>::try_from(v).ok(),
+                    Value::UInt16(v) => <$t>::try_from(v).ok(),
+                    Value::Int32(v) => <$t>::try_from(v).ok(),
+                    Value::UInt32(v) => <$t>::try_from(v).ok(),
+                    _ => None,
+                };
+                converted.ok_or_else(|| value_type_error(stringify!($t), &value))
+            }
+        }
+    }
+}
+
+value_try_into_integer!(u8);
+value_try_into_integer!(i16);
+value_try_into_integer!(u16);
+value_try_into_integer!(i32);
+value_try_into_integer!(u32);
+value_try_into_integer!(i64);
+value_try_into_integer!(u64);
+
+/// Converts any numeric value, since the SDK stores some fractional metadata as integers.
+impl TryFrom<Value> for f32 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<f32, Error> {
+        match value {
+            Value::Float(v) => Ok(v),
+            _ => value.to_f64().map(|v| v as f32).ok_or_else(|| value_type_error("f32", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<String, Error> {
+        match value {
+            Value::String(v) => Ok(v),
+            _ => Err(value_type_error("string", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = Error>> TryFrom<Value> for Vec<T> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Vec<T>, Error> {
+        match value {
+            Value::Array(v) => v.into_iter().map(T::try_from).collect(),
+            _ => Err(value_type_error("array", &value)),
+        }
+    }
+}
+
 impl std::iter::Iterator for MetadataIterator {
     type Item = (String, Value);
 
     fn next(&mut self) -> Option<(String, Value)> {
-        let mut value = Variant{
-            vt: _BlackmagicRawVariantType_blackmagicRawVariantTypeEmpty,
-            __bindgen_anon_1: Variant__bindgen_ty_1{
-                iVal: 0,
-            },
-        };
+        let mut value = Variant::empty();
 
         loop {
             unsafe {
-                match void_option_result(blackmagic_raw_metadata_iterator_next(self.implementation)) {
+                match void_option_result(blackmagic_raw_metadata_iterator_next(self.implementation), "IBlackmagicRawMetadataIterator::Next") {
                     Ok(Some(_)) => {},
                     _ => return None,
                 };
 
                 let mut buf: *mut Buffer = std::ptr::null_mut();
-                let key = match void_result(blackmagic_raw_metadata_iterator_get_key(self.implementation, &mut buf)) {
+                let key = match void_result(blackmagic_raw_metadata_iterator_get_key(self.implementation, &mut buf), "IBlackmagicRawMetadataIterator::GetKey") {
                     Ok(_) => {
                         let key = std::ffi::CStr::from_ptr(buffer_data(buf) as *const c_char).to_str().unwrap_or("").to_string();
                         buffer_release(buf);
@@ -643,39 +3337,21 @@ impl std::iter::Iterator for MetadataIterator {
 
                 VariantInit(&mut value);
 
-                match void_result(blackmagic_raw_metadata_iterator_get_data(self.implementation, &mut value)) {
+                match void_result(blackmagic_raw_metadata_iterator_get_data(self.implementation, &mut value), "IBlackmagicRawMetadataIterator::GetData") {
                     Ok(()) => {},
                     _ => return None,
                 };
 
-                let ret = match value.vt {
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeS16 => Value::Int16(value.__bindgen_anon_1.iVal),
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeU16 => Value::UInt16(value.__bindgen_anon_1.uiVal),
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeS32 => Value::Int32(value.__bindgen_anon_1.intVal),
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeU32 => Value::UInt32(value.__bindgen_anon_1.uintVal),
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeFloat32 => Value::Float(value.__bindgen_anon_1.fltVal),
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeString => {
-                        let mut buf: *mut Buffer = std::ptr::null_mut();
-                        blackmagic_raw_variant_get_string(&mut value, &mut buf);
-                        let s = std::ffi::CStr::from_ptr(buffer_data(buf) as *const c_char).to_str().unwrap_or("").to_string();
-                        buffer_release(buf);
-                        Value::String(s)
-                    },
-                    _BlackmagicRawVariantType_blackmagicRawVariantTypeSafeArray => match Value::new_from_safe_array(value.__bindgen_anon_1.parray) {
-                        Ok(Some(v)) => v,
-                        Ok(None) => {
-                            VariantClear(&mut value);
-                            continue;
-                        }
-                        Err(_) => {
-                            VariantClear(&mut value);
-                            return None;
-                        }
-                    },
-                    _ => {
+                let ret = match Value::new_from_variant(&mut value) {
+                    Ok(Some(v)) => v,
+                    Ok(None) => {
                         VariantClear(&mut value);
                         continue;
                     }
+                    Err(_) => {
+                        VariantClear(&mut value);
+                        return None;
+                    }
                 };
 
                 VariantClear(&mut value);
@@ -694,4 +3370,148 @@ mod tests {
     fn it_works() {
         let _ = Factory::new_from_path("/foo");
     }
+
+    #[test]
+    fn load_error_lists_attempts() {
+        let err = Factory::new_from_path("/foo").err().unwrap().to_string();
+        assert!(err.contains("/foo: directory does not exist"), "{}", err);
+    }
+
+    #[test]
+    fn error_codes() {
+        assert_eq!(ErrorCode::from_hresult(0x80000008u32 as HRESULT), ErrorCode::Fail);
+        assert_eq!(ErrorCode::from_hresult(0x80000010u32 as HRESULT), ErrorCode::Other(0x80000010u32 as HRESULT));
+        assert_eq!(ErrorCode::InvalidArg.hresult(), 0x80000003u32 as HRESULT);
+
+        let err = void_result(0x80000003u32 as HRESULT, "IBlackmagicRaw::OpenClip").unwrap_err().with_path("/clips/a.braw");
+        assert_eq!(err.code(), Some(ErrorCode::InvalidArg));
+        assert_eq!(err.to_string(), "blackmagic raw error: E_INVALIDARG from IBlackmagicRaw::OpenClip (/clips/a.braw)");
+    }
+
+    #[test]
+    fn pooled_resource_manager_reuses_buffers() {
+        let pool = PooledResourceManager::new(1024);
+        let a = pool.create_resource(1000, ResourceType::TYPE_BUFFER_CPU, ResourceUsage::USAGE_READ_CPU_WRITE_CPU).unwrap();
+        assert_eq!(a as usize % PooledResourceManager::ALIGNMENT, 0);
+        pool.release_resource(a, ResourceType::TYPE_BUFFER_CPU).unwrap();
+        let b = pool.create_resource(1000, ResourceType::TYPE_BUFFER_CPU, ResourceUsage::USAGE_READ_CPU_WRITE_CPU).unwrap();
+        assert_eq!(a, b);
+        let c = pool.create_resource(1000, ResourceType::TYPE_BUFFER_CPU, ResourceUsage::USAGE_READ_CPU_WRITE_CPU).unwrap();
+        assert_ne!(b, c);
+        pool.release_resource(b, ResourceType::TYPE_BUFFER_CPU).unwrap();
+        pool.release_resource(c, ResourceType::TYPE_BUFFER_CPU).unwrap();
+        assert!(pool.release_resource(c, ResourceType::TYPE_BUFFER_CPU).is_err());
+    }
+
+    #[test]
+    fn failed_trims_only_remove_new_files() {
+        let dir = std::env::temp_dir().join(format!("blackmagic-raw-trim-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let existing = dir.join("existing.braw");
+        let created = dir.join("created.braw");
+        std::fs::write(&existing, b"good trim").unwrap();
+
+        let existing_output = TrimOutput::new(&existing);
+        let created_output = TrimOutput::new(&created);
+        std::fs::write(&created, b"partial").unwrap();
+
+        let err = existing_output.finish(Err(Error::new(ErrorCode::Fail, "IBlackmagicRawCallback::TrimComplete"))).unwrap_err();
+        assert!(err.to_string().contains("existing.braw"), "{}", err);
+        assert!(existing.exists());
+        assert!(created_output.finish(Ok(())).is_ok());
+        assert!(created.exists());
+        assert!(created_output.finish(Err(Error::new(ErrorCode::Abort, "IBlackmagicRawCallback::TrimComplete"))).is_err());
+        assert!(!created.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_arrays_are_rejected() {
+        let message = |value: Value| match VariantArg::new(&value, "IBlackmagicRawClip::SetMetadata") {
+            Err(Error::InvalidValue{operation, message}) => {
+                assert_eq!(operation, "IBlackmagicRawClip::SetMetadata");
+                message
+            },
+            _ => panic!("{:?} wasn't rejected", value),
+        };
+        assert_eq!(message(Value::Array(Vec::new())), "empty arrays aren't supported");
+        assert!(message(Value::Array(vec![Value::UInt32(24000), Value::Float(1001.0)])).starts_with("array elements must all have the same type"));
+        assert_eq!(message(Value::Array(vec![Value::String("a".to_string())])), "arrays of strings aren't supported");
+
+        assert_eq!(array_elements(&[Value::UInt8(1), Value::UInt8(2)], Value::as_u8, "SetMetadata").unwrap(), vec![1, 2]);
+        assert!(array_elements(&[Value::UInt8(1), Value::Int16(2)], Value::as_u8, "SetMetadata").is_err());
+    }
+
+    #[test]
+    fn callback_panics_are_captured() {
+        struct Panicking(u32);
+        impl Callback for Panicking {
+            fn prepare_pipeline_complete(&mut self, _result: Result<(), Error>) {
+                assert!(in_callback());
+                self.0 += 1;
+                panic!("boom");
+            }
+        }
+
+        let callback = Arc::new(Mutex::new(Panicking(0)));
+        let panic = Arc::new(Mutex::new(None));
+        let context = Box::into_raw(Box::new(CallbackContext{
+            callback: callback.clone(),
+            panic: panic.clone(),
+        }));
+        unsafe {
+            callback_prepare_pipeline_complete(context, std::ptr::null_mut(), 0);
+            callback_prepare_pipeline_complete(context, std::ptr::null_mut(), 0);
+            callback_drop(context);
+        }
+        let payload = panic.lock().unwrap().take().unwrap();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+        assert_eq!(callback.lock().unwrap_or_else(|e| e.into_inner()).0, 1);
+        assert_eq!(Arc::strong_count(&callback), 1);
+        assert!(!in_callback());
+    }
+
+    #[test]
+    fn validate_checks_lists_then_ranges() {
+        let no_range = || Err(Error::new(ErrorCode::NotImpl, "IBlackmagicRawConstants::GetClipProcessingAttributeRange"));
+        let gammas = || Ok(vec![Value::String("Blackmagic Design Film".to_string()), Value::String("Rec.709".to_string())]);
+        assert!(validate(&Value::String("Rec.709".to_string()), ClipProcessingAttribute::GAMMA, "Pocket 6K", gammas(), no_range, "op").is_ok());
+        let err = validate(&Value::String("sRGB".to_string()), ClipProcessingAttribute::GAMMA, "Pocket 6K", gammas(), no_range, "op").unwrap_err();
+        assert_eq!(err.to_string(), "blackmagic raw error: invalid value passed to op: gamma \"sRGB\" isn't supported by Pocket 6K cameras, expected one of \"Blackmagic Design Film\", \"Rec.709\"");
+
+        let range = || Ok((Value::UInt32(2000), Value::UInt32(50000)));
+        assert!(validate(&Value::UInt32(5600), FrameProcessingAttribute::WHITE_BALANCE_KELVIN, "Pocket 6K", Ok(Vec::new()), range, "op").is_ok());
+        let err = validate(&Value::UInt32(90000), FrameProcessingAttribute::WHITE_BALANCE_KELVIN, "Pocket 6K", Ok(Vec::new()), range, "op").unwrap_err();
+        assert_eq!(err.to_string(), "blackmagic raw error: invalid value passed to op: white balance kelvin 90000 is outside the range supported by Pocket 6K cameras, 2000 to 50000");
+        assert!(validate(&Value::UInt32(90000), FrameProcessingAttribute::WHITE_BALANCE_KELVIN, "Pocket 6K", Ok(Vec::new()), no_range, "op").is_ok());
+
+        // Failed queries, for example for an unknown camera type, aren't treated as unconstrained.
+        let unknown_camera = Err(Error::new(ErrorCode::InvalidArg, "IBlackmagicRawConstants::GetFrameProcessingAttributeList"));
+        let err = validate(&Value::UInt32(5600), FrameProcessingAttribute::WHITE_BALANCE_KELVIN, "Pocket 7K", unknown_camera, range, "op").unwrap_err();
+        assert_eq!(err.code(), Some(ErrorCode::InvalidArg));
+        let failed_range = || Err(Error::new(ErrorCode::Fail, "IBlackmagicRawConstants::GetFrameProcessingAttributeRange"));
+        let err = validate(&Value::UInt32(5600), FrameProcessingAttribute::WHITE_BALANCE_KELVIN, "Pocket 6K", Ok(Vec::new()), failed_range, "op").unwrap_err();
+        assert_eq!(err.code(), Some(ErrorCode::Fail));
+    }
+
+    #[test]
+    fn value_conversions() {
+        let rate = Value::from(vec![24000u32, 1001]);
+        assert_eq!(rate.to_string(), "[24000, 1001]");
+        assert_eq!(rate.to_u32_vec(), Some(vec![24000, 1001]));
+        assert_eq!(rate.to_f32_vec(), None);
+        assert_eq!(Vec::<u64>::try_from(rate.clone()).unwrap(), vec![24000, 1001]);
+
+        assert_eq!(Value::from("12A").as_str(), Some("12A"));
+        assert_eq!(Value::from("12A").to_string(), "12A");
+        assert_eq!(Value::from(1.5f32).as_f32(), Some(1.5));
+        assert_eq!(Value::from(1.5f32).as_u32(), None);
+
+        assert_eq!(u8::try_from(Value::UInt16(200)).unwrap(), 200);
+        assert_eq!(f32::try_from(Value::Int16(-2)).unwrap(), -2.0);
+        assert_eq!(u16::try_from(Value::Int32(-1)).unwrap_err().to_string(), "blackmagic raw error: invalid value passed to Value::try_into: expected u16, found -1");
+        assert!(String::try_from(Value::UInt8(1)).is_err());
+        assert_eq!(String::try_from(Value::from("take")).unwrap(), "take");
+    }
 }
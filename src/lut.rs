@@ -0,0 +1,443 @@
+//! Post 3D LUTs, which are applied to frames after processing, and conversion to and from the
+//! `.cube` format.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::*;
+
+/// A 3D LUT attached to a clip, either embedded in it or from a sidecar file. Obtain it with
+/// `ClipProcessingAttributes::get_post_3d_lut`.
+pub struct Post3DLut {
+    implementation: *mut IBlackmagicRawPost3DLUT,
+}
+
+unsafe impl Send for Post3DLut {}
+
+impl Drop for Post3DLut {
+    fn drop(&mut self) {
+        unsafe {
+            blackmagic_raw_unknown_release(self.implementation as *mut IUnknown);
+        }
+    }
+}
+
+impl Post3DLut {
+    pub fn get_name(&mut self) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_post_3d_lut_get_name(self.implementation, &mut buf), "IBlackmagicRawPost3DLUT::GetName")?;
+            Ok(buffer_to_string(buf))
+        }
+    }
+
+    pub fn get_title(&mut self) -> Result<String, Error> {
+        let mut buf: *mut Buffer = std::ptr::null_mut();
+        unsafe {
+            void_result(blackmagic_raw_post_3d_lut_get_title(self.implementation, &mut buf), "IBlackmagicRawPost3DLUT::GetTitle")?;
+            Ok(buffer_to_string(buf))
+        }
+    }
+
+    /// Returns the number of points along each axis of the LUT.
+    pub fn get_size(&mut self) -> Result<u32, Error> {
+        let mut ret = 0;
+        unsafe {
+            void_result(blackmagic_raw_post_3d_lut_get_size(self.implementation, &mut ret), "IBlackmagicRawPost3DLUT::GetSize")?;
+        }
+        return Ok(ret)
+    }
+
+    /// Returns the LUT's size³ RGB triplets, with red changing fastest as in the `.cube` format.
+    pub fn get_data(&mut self) -> Result<&[f32], Error> {
+        let size = self.get_size()? as usize;
+        let len = size * size * size * 3;
+        let mut resource: *mut c_void = std::ptr::null_mut();
+        let mut size_bytes = 0;
+        unsafe {
+            void_result(blackmagic_raw_post_3d_lut_get_resource_cpu(self.implementation, &mut resource), "IBlackmagicRawPost3DLUT::GetResourceCPU")?;
+            void_result(blackmagic_raw_post_3d_lut_get_resource_size_bytes(self.implementation, &mut size_bytes), "IBlackmagicRawPost3DLUT::GetResourceSizeBytes")?;
+            if len == 0 {
+                return Ok(&[]);
+            }
+            if resource.is_null() || (size_bytes as usize) < len * std::mem::size_of::<f32>() {
+                return Err(Error::new(ErrorCode::Unexpected, "IBlackmagicRawPost3DLUT::GetResourceCPU"));
+            }
+            Ok(std::slice::from_raw_parts(resource as *const f32, len))
+        }
+    }
+
+    /// Copies the LUT so that it can be written as a `.cube` file. The title falls back to the
+    /// LUT's name if it doesn't have one.
+    pub fn to_cube(&mut self) -> Result<CubeLut, Error> {
+        let mut title = self.get_title()?;
+        if title.is_empty() {
+            title = self.get_name()?;
+        }
+        Ok(CubeLut{
+            title: title,
+            size: self.get_size()?,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data: self.get_data()?.to_vec(),
+        })
+    }
+}
+
+impl ClipProcessingAttributes {
+    /// Returns the 3D LUT selected by the post 3D LUT mode, or `None` if it's disabled or the clip
+    /// doesn't have one.
+    pub fn get_post_3d_lut(&mut self) -> Result<Option<Post3DLut>, Error> {
+        let mut lut: *mut IBlackmagicRawPost3DLUT = std::ptr::null_mut();
+        unsafe {
+            if void_option_result(blackmagic_raw_clip_processing_attributes_get_post_3d_lut(self.implementation, &mut lut), "IBlackmagicRawClipProcessingAttributes::GetPost3DLUT")?.is_none() || lut.is_null() {
+                return Ok(None);
+            }
+        }
+        return Ok(Some(Post3DLut{
+            implementation: lut,
+        }))
+    }
+
+    /// Attaches a LUT as the clip's sidecar 3D LUT and selects it with the post 3D LUT mode. `name`
+    /// is typically the LUT's file name. LUTs with a domain other than 0 to 1 are resampled, since
+    /// the SDK doesn't support one.
+    ///
+    /// All of the values are validated before any are set, but if the SDK rejects one of them, the
+    /// attributes set before it keep their new values.
+    pub fn set_sidecar_post_3d_lut(&mut self, name: &str, lut: &CubeLut) -> Result<(), Error> {
+        let operation = "IBlackmagicRawClipProcessingAttributes::SetClipAttribute";
+        let size = lut.size as usize;
+        if lut.data.len() != size * size * size * 3 {
+            return Err(Error::InvalidValue{
+                operation: operation,
+                message: format!("a LUT of size {} needs {} values, not {}", size, size * size * size * 3, lut.data.len()),
+            });
+        }
+        if lut.size > u16::MAX as u32 {
+            return Err(Error::InvalidValue{
+                operation: operation,
+                message: format!("LUT size {} is too large", lut.size),
+            });
+        }
+        let resampled;
+        let lut = if lut.has_unit_domain() {
+            lut
+        } else {
+            resampled = lut.with_unit_domain();
+            &resampled
+        };
+        let attributes = vec![
+            (ClipProcessingAttribute::SIDECAR_POST_3D_LUT_NAME, self.prepare_attribute(ClipProcessingAttribute::SIDECAR_POST_3D_LUT_NAME, &Value::String(name.to_string()))?),
+            (ClipProcessingAttribute::SIDECAR_POST_3D_LUT_TITLE, self.prepare_attribute(ClipProcessingAttribute::SIDECAR_POST_3D_LUT_TITLE, &Value::String(lut.title.clone()))?),
+            (ClipProcessingAttribute::SIDECAR_POST_3D_LUT_SIZE, self.prepare_attribute(ClipProcessingAttribute::SIDECAR_POST_3D_LUT_SIZE, &Value::UInt16(lut.size as u16))?),
+            (ClipProcessingAttribute::SIDECAR_POST_3D_LUT_DATA, VariantArg::new_f32_array(&lut.data, operation)?),
+            (ClipProcessingAttribute::POST_3D_LUT_MODE, self.prepare_attribute(ClipProcessingAttribute::POST_3D_LUT_MODE, &Value::String("Sidecar".to_string()))?),
+        ];
+        for (attribute, mut value) in attributes {
+            self.set_variant(attribute, &mut value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A 3D LUT in the form used by `.cube` files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub title: String,
+    /// The number of points along each axis.
+    pub size: u32,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    /// size³ RGB triplets, with red changing fastest.
+    pub data: Vec<f32>,
+}
+
+/// An error encountered while parsing a `.cube` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeParseError {
+    /// The 1-based line number that the error was found on.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CubeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid cube file: line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CubeParseError {}
+
+fn parse_floats(tokens: &[&str], count: usize, line: usize) -> Result<Vec<f32>, CubeParseError> {
+    if tokens.len() != count {
+        return Err(CubeParseError{
+            line: line,
+            message: format!("expected {} values, found {}", count, tokens.len()),
+        });
+    }
+    tokens.iter().map(|t| t.parse::<f32>().map_err(|_| CubeParseError{
+        line: line,
+        message: format!("{:?} isn't a number", t),
+    })).collect()
+}
+
+impl FromStr for CubeLut {
+    type Err = CubeParseError;
+
+    fn from_str(s: &str) -> Result<CubeLut, CubeParseError> {
+        CubeLut::parse(s)
+    }
+}
+
+impl CubeLut {
+    /// The largest size allowed by the `.cube` specification.
+    pub const MAX_SIZE: u32 = 256;
+
+    /// Parses a 3D LUT in the Adobe / Resolve `.cube` format. 1D LUTs aren't supported.
+    pub fn parse(s: &str) -> Result<CubeLut, CubeParseError> {
+        let mut lut = CubeLut{
+            title: String::new(),
+            size: 0,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data: Vec::new(),
+        };
+        let mut expected = 0;
+        let mut last_line = 0;
+        let mut domain_line = 0;
+
+        for (i, line) in s.lines().enumerate() {
+            let line_number = i + 1;
+            last_line = line_number;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: String| CubeParseError{
+                line: line_number,
+                message: message,
+            };
+
+            if !tokens[0].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                if lut.size == 0 {
+                    return Err(error("LUT data must come after LUT_3D_SIZE".to_string()));
+                }
+                if lut.data.len() >= expected {
+                    return Err(error(format!("too many entries for a LUT of size {}", lut.size)));
+                }
+                lut.data.extend(parse_floats(&tokens, 3, line_number)?);
+                continue;
+            }
+            if !lut.data.is_empty() {
+                return Err(error(format!("{} must come before the LUT data", tokens[0])));
+            }
+            match tokens[0] {
+                "TITLE" => {
+                    lut.title = line["TITLE".len()..].trim().trim_matches('"').to_string();
+                },
+                "LUT_3D_SIZE" => {
+                    if lut.size != 0 {
+                        return Err(error("LUT_3D_SIZE is given more than once".to_string()));
+                    }
+                    let size = match (tokens.len(), tokens.get(1).and_then(|t| t.parse::<u32>().ok())) {
+                        (2, Some(size)) => size,
+                        _ => return Err(error("LUT_3D_SIZE must be followed by a single integer".to_string())),
+                    };
+                    if !(2..=CubeLut::MAX_SIZE).contains(&size) {
+                        return Err(error(format!("LUT_3D_SIZE must be between 2 and {}, not {}", CubeLut::MAX_SIZE, size)));
+                    }
+                    lut.size = size;
+                    expected = (size * size * size * 3) as usize;
+                    lut.data.reserve(expected);
+                },
+                "LUT_1D_SIZE" => return Err(error("1D LUTs aren't supported".to_string())),
+                "DOMAIN_MIN" => {
+                    domain_line = line_number;
+                    let v = parse_floats(&tokens[1..], 3, line_number)?;
+                    lut.domain_min = [v[0], v[1], v[2]];
+                },
+                "DOMAIN_MAX" => {
+                    domain_line = line_number;
+                    let v = parse_floats(&tokens[1..], 3, line_number)?;
+                    lut.domain_max = [v[0], v[1], v[2]];
+                },
+                // Resolve's form of the domain, which applies to all three channels.
+                "LUT_3D_INPUT_RANGE" => {
+                    domain_line = line_number;
+                    let v = parse_floats(&tokens[1..], 2, line_number)?;
+                    lut.domain_min = [v[0]; 3];
+                    lut.domain_max = [v[1]; 3];
+                },
+                // Other keywords, such as LUT_1D_INPUT_RANGE, don't affect 3D LUTs.
+                _ => {},
+            }
+        }
+
+        if lut.domain_min.iter().zip(lut.domain_max.iter()).any(|(min, max)| min >= max) {
+            return Err(CubeParseError{
+                line: domain_line,
+                message: "DOMAIN_MIN must be less than DOMAIN_MAX".to_string(),
+            });
+        }
+
+        if lut.size == 0 {
+            return Err(CubeParseError{
+                line: last_line,
+                message: "missing LUT_3D_SIZE".to_string(),
+            });
+        }
+        if lut.data.len() != expected {
+            return Err(CubeParseError{
+                line: last_line,
+                message: format!("expected {} entries for a LUT of size {}, found {}", expected / 3, lut.size, lut.data.len() / 3),
+            });
+        }
+        Ok(lut)
+    }
+
+    fn has_unit_domain(&self) -> bool {
+        self.domain_min == [0.0; 3] && self.domain_max == [1.0; 3]
+    }
+
+    /// Looks up the output for a position within the LUT, where each coordinate ranges from 0 to
+    /// size - 1, using trilinear interpolation.
+    fn sample(&self, position: [f32; 3]) -> [f32; 3] {
+        let size = self.size as usize;
+        let mut lo = [0; 3];
+        let mut hi = [0; 3];
+        let mut t = [0.0; 3];
+        for c in 0..3 {
+            let p = position[c].max(0.0).min((size - 1) as f32);
+            lo[c] = p.floor() as usize;
+            hi[c] = std::cmp::min(lo[c] + 1, size - 1);
+            t[c] = p - lo[c] as f32;
+        }
+        let entry = |r: usize, g: usize, b: usize| 3 * (r + size * (g + size * b));
+        let mut ret = [0.0; 3];
+        for (i, &b) in [lo[2], hi[2]].iter().enumerate() {
+            for (j, &g) in [lo[1], hi[1]].iter().enumerate() {
+                for (k, &r) in [lo[0], hi[0]].iter().enumerate() {
+                    let weight = (if k == 0 { 1.0 - t[0] } else { t[0] }) * (if j == 0 { 1.0 - t[1] } else { t[1] }) * (if i == 0 { 1.0 - t[2] } else { t[2] });
+                    let e = entry(r, g, b);
+                    for (c, v) in ret.iter_mut().enumerate() {
+                        *v += weight * self.data[e + c];
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Returns an equivalent LUT with a domain of 0 to 1. Inputs outside of the original domain are
+    /// clamped to it.
+    pub fn with_unit_domain(&self) -> CubeLut {
+        let size = self.size as usize;
+        let scale = (size - 1) as f32;
+        let mut data = Vec::with_capacity(self.data.len());
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let mut position = [0.0; 3];
+                    for (c, &i) in [r, g, b].iter().enumerate() {
+                        let x = i as f32 / scale;
+                        position[c] = (x - self.domain_min[c]) / (self.domain_max[c] - self.domain_min[c]) * scale;
+                    }
+                    data.extend_from_slice(&self.sample(position));
+                }
+            }
+        }
+        CubeLut{
+            title: self.title.clone(),
+            size: self.size,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data: data,
+        }
+    }
+
+    /// Writes the LUT in the `.cube` format.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let size = self.size as usize;
+        if self.data.len() != size * size * size * 3 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a LUT of size {} needs {} values, not {}", size, size * size * size * 3, self.data.len())));
+        }
+        if !self.title.is_empty() {
+            // The format has no way to escape quotes.
+            writeln!(w, "TITLE \"{}\"", self.title.replace('"', "'"))?;
+        }
+        writeln!(w, "LUT_3D_SIZE {}", self.size)?;
+        if !self.has_unit_domain() {
+            writeln!(w, "DOMAIN_MIN {:.6} {:.6} {:.6}", self.domain_min[0], self.domain_min[1], self.domain_min[2])?;
+            writeln!(w, "DOMAIN_MAX {:.6} {:.6} {:.6}", self.domain_max[0], self.domain_max[1], self.domain_max[2])?;
+        }
+        writeln!(w)?;
+        for rgb in self.data.chunks(3) {
+            writeln!(w, "{:.6} {:.6} {:.6}", rgb[0], rgb[1], rgb[2])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_lut_write() {
+        let mut lut = CubeLut{
+            title: "Film to \"Video\"".to_string(),
+            size: 2,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data: Vec::new(),
+        };
+        for b in 0..2 {
+            for g in 0..2 {
+                for r in 0..2 {
+                    lut.data.extend_from_slice(&[r as f32, g as f32 * 0.5, b as f32 * 0.25]);
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        lut.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "TITLE \"Film to 'Video'\"\nLUT_3D_SIZE 2\n\n\
+            0.000000 0.000000 0.000000\n1.000000 0.000000 0.000000\n0.000000 0.500000 0.000000\n1.000000 0.500000 0.000000\n\
+            0.000000 0.000000 0.250000\n1.000000 0.000000 0.250000\n0.000000 0.500000 0.250000\n1.000000 0.500000 0.250000\n");
+
+        lut.data.pop();
+        assert!(lut.write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn cube_lut_parse() {
+        let lut: CubeLut = "# Created by hand\r\nTITLE \"Half\"\r\nLUT_3D_SIZE 2\r\nDOMAIN_MIN 0 0 0\r\nDOMAIN_MAX 2 2 2\r\n\r\n\
+            0 0 0\r\n1 0 0\r\n0 1 0\r\n1 1 0\r\n0 0 1\r\n1 0 1\r\n0 1 1\r\n1 1 1\r\n".parse().unwrap();
+        assert_eq!(lut.title, "Half");
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.domain_max, [2.0; 3]);
+        assert_eq!(lut.data.len(), 24);
+
+        let mut out = Vec::new();
+        lut.write(&mut out).unwrap();
+        assert_eq!(CubeLut::parse(std::str::from_utf8(&out).unwrap()).unwrap(), lut);
+
+        // Over the doubled domain, this maps 1.0 to 0.5.
+        let unit = lut.with_unit_domain();
+        assert_eq!(unit.domain_max, [1.0; 3]);
+        assert_eq!(&unit.data[3..6], &[0.5, 0.0, 0.0]);
+        assert_eq!(&unit.data[21..24], &[0.5, 0.5, 0.5]);
+
+        let error = |s: &str| CubeLut::parse(s).unwrap_err();
+        assert_eq!(error("LUT_3D_SIZE 2\n0 0 0\n1 0\n"), CubeParseError{ line: 3, message: "expected 3 values, found 2".to_string() });
+        assert_eq!(error("LUT_3D_SIZE 2\n0 0 0\n1 x 0\n"), CubeParseError{ line: 3, message: "\"x\" isn't a number".to_string() });
+        assert_eq!(error("0 0 0\n").line, 1);
+        assert_eq!(error("LUT_1D_SIZE 16\n").line, 1);
+        assert_eq!(error("TITLE \"x\"\nLUT_3D_SIZE 1\n").line, 2);
+        assert_eq!(error("LUT_3D_SIZE 2\nDOMAIN_MIN 1 1 1\nDOMAIN_MAX 1 1 1\n").line, 3);
+        assert!(CubeLut::parse("DOMAIN_MIN 2 2 2\nDOMAIN_MAX 4 4 4\nLUT_3D_SIZE 2\n0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n").is_ok());
+        assert_eq!(error("LUT_3D_SIZE 2\n0 0 0\n").to_string(), "invalid cube file: line 2: expected 8 entries for a LUT of size 2, found 1");
+    }
+}
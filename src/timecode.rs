@@ -0,0 +1,241 @@
+//! SMPTE timecode, including drop-frame timecode for 29.97 and 59.94 fps clips.
+
+use std::ops::{Add, Sub};
+
+use super::*;
+
+/// A SMPTE timecode. It's stored as a number of frames since midnight, so arithmetic wraps around
+/// at 24 hours like a clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timecode {
+    frame_number: u64,
+    rate: u32,
+    drop_frame: bool,
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidValue{
+        operation: "Timecode",
+        message: message,
+    }
+}
+
+// `is_multiple_of` would need Rust 1.87.
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+impl Timecode {
+    /// Creates a timecode from a number of frames since midnight. `rate` is the nominal frame rate,
+    /// for example 30 for 29.97 fps, and drop-frame timecode requires it to be a multiple of 30.
+    pub fn new(frame_number: u64, rate: u32, drop_frame: bool) -> Result<Timecode, Error> {
+        if rate == 0 || (drop_frame && rate % 30 != 0) {
+            return Err(invalid(format!("{} fps doesn't support {}timecode", rate, if drop_frame { "drop-frame " } else { "" })));
+        }
+        let ret = Timecode{
+            frame_number: 0,
+            rate: rate,
+            drop_frame: drop_frame,
+        };
+        Ok(ret.with_frame_number(frame_number as i128))
+    }
+
+    /// Creates a timecode from its displayed hours, minutes, seconds, and frames. Drop-frame
+    /// timecode skips some frame numbers at the start of each minute, and those are rejected.
+    pub fn from_components(hours: u32, minutes: u32, seconds: u32, frames: u32, rate: u32, drop_frame: bool) -> Result<Timecode, Error> {
+        let ret = Timecode::new(0, rate, drop_frame)?;
+        if hours >= 24 || minutes >= 60 || seconds >= 60 || frames >= rate {
+            return Err(invalid(format!("{:02}:{:02}:{:02}:{:02} isn't a valid timecode at {} fps", hours, minutes, seconds, frames, rate)));
+        }
+        let total_minutes = hours as u64 * 60 + minutes as u64;
+        let mut frame_number = (total_minutes * 60 + seconds as u64) * rate as u64 + frames as u64;
+        if drop_frame {
+            let dropped = ret.dropped_per_minute();
+            if seconds == 0 && (frames as u64) < dropped && minutes % 10 != 0 {
+                return Err(invalid(format!("{:02}:{:02}:{:02};{:02} is skipped by drop-frame timecode", hours, minutes, seconds, frames)));
+            }
+            frame_number -= dropped * (total_minutes - total_minutes / 10);
+        }
+        Ok(Timecode{
+            frame_number: frame_number,
+            ..ret
+        })
+    }
+
+    /// Parses a timecode such as "01:00:00:00", as returned by the SDK. A ';' or '.' before the
+    /// frames marks drop-frame timecode, regardless of `drop_frame`.
+    pub fn parse(s: &str, rate: u32, drop_frame: bool) -> Result<Timecode, Error> {
+        let s = s.trim();
+        let parts: Vec<&str> = s.split(&[':', ';', '.'][..]).collect();
+        let components: Vec<u32> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+        if parts.len() != 4 || components.len() != 4 {
+            return Err(invalid(format!("{:?} isn't formatted as HH:MM:SS:FF", s)));
+        }
+        let drop_frame = drop_frame || s.contains(';') || s.contains('.');
+        Timecode::from_components(components[0], components[1], components[2], components[3], rate, drop_frame)
+    }
+
+    /// The number of frames since midnight.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+
+    /// The nominal frame rate, for example 30 for 29.97 fps.
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn is_drop_frame(&self) -> bool {
+        self.drop_frame
+    }
+
+    fn dropped_per_minute(&self) -> u64 {
+        if self.drop_frame {
+            (self.rate / 15) as u64
+        } else {
+            0
+        }
+    }
+
+    fn frames_per_day(&self) -> u64 {
+        let frames_per_ten_minutes = self.rate as u64 * 600 - self.dropped_per_minute() * 9;
+        frames_per_ten_minutes * 6 * 24
+    }
+
+    fn with_frame_number(self, frame_number: i128) -> Timecode {
+        Timecode{
+            frame_number: frame_number.rem_euclid(self.frames_per_day() as i128) as u64,
+            ..self
+        }
+    }
+
+    /// Returns the number of frames from `other` to `self`, going forward and wrapping at midnight.
+    /// Both timecodes must have the same rate and drop-frame setting.
+    pub fn frames_since(&self, other: &Timecode) -> Result<u64, Error> {
+        if self.rate != other.rate || self.drop_frame != other.drop_frame {
+            return Err(invalid(format!("{} and {} don't have the same frame rate and drop-frame setting", self, other)));
+        }
+        let frames_per_day = self.frames_per_day();
+        Ok((self.frame_number + frames_per_day - other.frame_number) % frames_per_day)
+    }
+
+    /// Returns the displayed hours, minutes, seconds, and frames.
+    pub fn components(&self) -> (u32, u32, u32, u32) {
+        let rate = self.rate as u64;
+        let mut n = self.frame_number;
+        let dropped = self.dropped_per_minute();
+        if dropped > 0 {
+            let frames_per_minute = rate * 60 - dropped;
+            let frames_per_ten_minutes = rate * 600 - dropped * 9;
+            let tens = n / frames_per_ten_minutes;
+            let remainder = n % frames_per_ten_minutes;
+            n += dropped * 9 * tens;
+            if remainder >= dropped {
+                n += dropped * ((remainder - dropped) / frames_per_minute);
+            }
+        }
+        let frames = n % rate;
+        let seconds = n / rate;
+        ((seconds / 3600) as u32, (seconds / 60 % 60) as u32, (seconds % 60) as u32, frames as u32)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (hours, minutes, seconds, frames) = self.components();
+        write!(f, "{:02}:{:02}:{:02}{}{:02}", hours, minutes, seconds, if self.drop_frame { ';' } else { ':' }, frames)
+    }
+}
+
+impl Add<i64> for Timecode {
+    type Output = Timecode;
+
+    fn add(self, frames: i64) -> Timecode {
+        self.with_frame_number(self.frame_number as i128 + frames as i128)
+    }
+}
+
+impl Sub<i64> for Timecode {
+    type Output = Timecode;
+
+    fn sub(self, frames: i64) -> Timecode {
+        self.with_frame_number(self.frame_number as i128 - frames as i128)
+    }
+}
+
+impl Clip {
+    /// Returns whether the clip's timecode is drop-frame, using `query_timecode_info` if it's
+    /// available and the SDK's formatting otherwise.
+    fn is_drop_frame_timecode(&mut self, timecode: &str) -> Result<bool, Error> {
+        Ok(match self.query_timecode_info()? {
+            Some((_, drop_frame)) => drop_frame,
+            None => timecode.contains(';'),
+        })
+    }
+
+    /// Returns the timecode of a frame.
+    pub fn timecode_for_frame(&mut self, frame_index: u64) -> Result<Timecode, Error> {
+        let s = self.get_timecode_for_frame(frame_index)?;
+        let rate = self.frame_rate()?.nominal();
+        let drop_frame = self.is_drop_frame_timecode(&s)?;
+        Timecode::parse(&s, rate, drop_frame)
+    }
+
+    /// Returns the index of the frame with the given timecode, for example to turn an edit's in
+    /// and out points into arguments for `create_job_read_frame`.
+    pub fn frame_for_timecode(&mut self, timecode: &Timecode) -> Result<u64, Error> {
+        let start = self.timecode_for_frame(0)?;
+        if start.rate != timecode.rate || start.drop_frame != timecode.drop_frame {
+            return Err(invalid(format!("{} doesn't match the clip's {} fps {}timecode", timecode, start.rate, if start.drop_frame { "drop-frame " } else { "" })));
+        }
+        let frame_index = timecode.frames_since(&start)?;
+        let frame_count = self.get_frame_count()?;
+        if frame_index >= frame_count {
+            return Err(invalid(format!("{} is outside the clip, which runs from {} to {}", timecode, start, start + (frame_count as i64 - 1))));
+        }
+        Ok(frame_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_frame_timecode() {
+        let tc = Timecode::parse("00:00:59;29", 30, false).unwrap();
+        assert!(tc.is_drop_frame());
+        assert_eq!(tc.frame_number(), 1799);
+        assert_eq!((tc + 1).to_string(), "00:01:00;02");
+        assert_eq!((tc + 1).frame_number(), 1800);
+        assert!(Timecode::parse("00:01:00;00", 30, true).is_err());
+        assert_eq!(Timecode::parse("00:10:00;00", 30, true).unwrap().frame_number(), 17982);
+        assert_eq!(Timecode::parse("01:00:00;00", 30, true).unwrap().frame_number(), 107892);
+        assert_eq!(Timecode::parse("00:01:00;04", 60, true).unwrap().frame_number(), 3600);
+
+        for n in 0..40000 {
+            let tc = Timecode::new(n, 30, true).unwrap();
+            assert_eq!(Timecode::parse(&tc.to_string(), 30, true).unwrap(), tc);
+        }
+    }
+
+    #[test]
+    fn timecode_arithmetic() {
+        let tc = Timecode::parse("01:00:00:00", 24, false).unwrap();
+        assert_eq!(tc.frame_number(), 86400);
+        assert_eq!((tc - 1).to_string(), "00:59:59:23");
+        assert_eq!((tc + 48).frames_since(&tc).unwrap(), 48);
+        assert!(tc.frames_since(&Timecode::new(86400, 25, false).unwrap()).is_err());
+
+        let midnight = Timecode::parse("23:59:59:24", 25, false).unwrap() + 1;
+        assert_eq!(midnight.to_string(), "00:00:00:00");
+        assert_eq!(midnight.frames_since(&Timecode::new(2159999, 25, false).unwrap()).unwrap(), 1);
+
+        assert!(Timecode::parse("01:00:00", 24, false).is_err());
+        assert!(Timecode::parse("01:00:00:24", 24, false).is_err());
+        assert!(Timecode::new(0, 25, true).is_err());
+
+        // Large rates, frame numbers, and offsets wrap instead of overflowing.
+        assert_eq!(Timecode::parse("23:59:59:99999", 100000, false).unwrap().frame_number(), 8_639_999_999);
+        assert_eq!(Timecode::new(u64::MAX, 24, false).unwrap().frame_number(), u64::MAX % 2_073_600);
+        assert_eq!((midnight + i64::MAX - i64::MAX).frame_number(), 0);
+        assert_eq!((midnight - i64::MIN).frame_number(), (i64::MIN as i128).unsigned_abs() as u64 % 2_160_000);
+    }
+}